@@ -0,0 +1,218 @@
+//! The level editor.
+//!
+//! The editor works on the map of a `Game`, so levels are test-played with the very same sliding
+//! logic as in the game itself.
+
+use std::cmp;
+use std::fs::File;
+use std::io::{Read, Write};
+
+use termion;
+
+use super::{Direction, Game, Mode};
+use grid::{Grid, Tile};
+use solver::{self, Verdict};
+
+/// The largest size of a new map, which is the size of the shipped levels.
+const MAX_SIZE: (u16, u16) = (78, 33);
+
+/// Build an empty map: ice surrounded by walls, with the start in the top-left corner and the goal
+/// in the bottom-right corner.
+fn blank(width: u16, height: u16) -> Grid {
+    let mut map = Grid::new(width, height, Tile::Ice);
+
+    for x in 0..width {
+        map.set(x, 0, Tile::Wall(b'#'));
+        map.set(x, height - 1, Tile::Wall(b'#'));
+    }
+    for y in 0..height {
+        map.set(0, y, Tile::Wall(b'#'));
+        map.set(width - 1, y, Tile::Wall(b'#'));
+    }
+    map.set(1, 1, Tile::Start);
+    map.set(width - 2, height - 2, Tile::Goal);
+
+    map
+}
+
+impl<R: Read, W: Write> Game<R, W> {
+    /// Run the level editor on a map file.
+    ///
+    /// If the file does not exist, the editor starts from an empty map fitting in the terminal.
+    pub fn edit(&mut self, path: &str) {
+        let mut text = Vec::new();
+        self.map = match File::open(path).and_then(|mut file| file.read_to_end(&mut text)) {
+            Ok(_) => Grid::parse(&text),
+            Err(_) => {
+                let (term_width, term_height) = termion::terminal_size().unwrap_or((80, 24));
+                blank(cmp::max(cmp::min(term_width, MAX_SIZE.0), 4),
+                      cmp::max(cmp::min(term_height.saturating_sub(1), MAX_SIZE.1), 4))
+            },
+        };
+        self.mode = Mode::Edit;
+        self.init();
+
+        // The map as last saved, to tell whether quitting would lose edits.
+        let mut saved = self.map.to_bytes();
+
+        loop {
+            let mut b = [0];
+            self.stdin.read(&mut b).unwrap();
+
+            match b[0] {
+                b'h' | b'a' => self.move_cursor(Direction::Left),
+                b'j' | b's' => self.move_cursor(Direction::Down),
+                b'k' | b'w' => self.move_cursor(Direction::Up),
+                b'l' | b'd' => self.move_cursor(Direction::Right),
+                b'#' | b' ' | b'@' | b'*' | b'.' => self.paint(Tile::from_byte(b[0])),
+                b'$' => self.paint_crate(),
+                b't' => self.test_play(),
+                b'S' | b'F' => if self.save(path, b[0] == b'F') {
+                    saved = self.map.to_bytes();
+                },
+                b'q' => {
+                    if self.map.to_bytes() == saved {
+                        break;
+                    }
+
+                    self.message("The map has unsaved changes! Press q again to quit anyway.");
+                    self.stdin.read(&mut b).unwrap();
+                    if b[0] == b'q' {
+                        break;
+                    }
+                    self.update();
+                },
+                _ => {},
+            }
+        }
+
+        self.mode = Mode::Play;
+    }
+
+    /// Move the editor cursor, keeping it inside the map.
+    fn move_cursor(&mut self, dir: Direction) {
+        if let Some((x, y)) = dir.step(self.x, self.y) {
+            if self.map.contains(x, y) {
+                self.x = x;
+                self.y = y;
+
+                if self.follow() {
+                    self.draw();
+                } else {
+                    self.update();
+                }
+            }
+        }
+    }
+
+    /// Paint a tile under the cursor.
+    ///
+    /// There is only one start, so painting a new one replaces the old one.
+    fn paint(&mut self, tile: Tile) {
+        if tile == Tile::Start {
+            while let Some((x, y)) = self.map.find(Tile::Start) {
+                self.map.set(x, y, Tile::Ice);
+                self.draw_tile(x, y);
+            }
+        }
+
+        let (x, y) = (self.x, self.y);
+        self.map.set(x, y, tile);
+        self.map.set_crate(x, y, false);
+        self.draw_tile(x, y);
+        self.update();
+    }
+
+    /// Put a crate under the cursor.
+    ///
+    /// Crates lie on ice or on targets, so any other tile is turned into ice, except for the start
+    /// which cannot hold a crate.
+    fn paint_crate(&mut self) {
+        let (x, y) = (self.x, self.y);
+
+        match self.map.get(x, y) {
+            Tile::Start => {
+                self.message("There cannot be a crate on the start.");
+                return;
+            },
+            Tile::Ice | Tile::Target => {},
+            _ => self.map.set(x, y, Tile::Ice),
+        }
+        self.map.set_crate(x, y, true);
+        self.draw_tile(x, y);
+        self.update();
+    }
+
+    /// Play the map from its start until it is finished or the player goes back to editing.
+    ///
+    /// The map is restored afterwards, so pushed crates go back to where they were.
+    fn test_play(&mut self) {
+        let (cursor_x, cursor_y) = (self.x, self.y);
+        let map = self.map.clone();
+        let (x, y) = self.start_position();
+
+        self.mode = Mode::Test;
+        self.x = x;
+        self.y = y;
+        self.moves = 0;
+        self.follow();
+        self.draw();
+
+        loop {
+            let mut b = [0];
+            self.stdin.read(&mut b).unwrap();
+
+            let reached = match b[0] {
+                b'h' | b'a' => self.slide(Direction::Left),
+                b'j' | b's' => self.slide(Direction::Down),
+                b'k' | b'w' => self.slide(Direction::Up),
+                b'l' | b'd' => self.slide(Direction::Right),
+                b'r' => {
+                    self.map = map.clone();
+                    self.moves = 0;
+                    self.init();
+                    false
+                },
+                b'q' | b'm' => break,
+                _ => false,
+            };
+
+            if reached {
+                let moves = self.moves;
+                self.message(&format!("Finished in {} moves! Press any key to go back to editing.", moves));
+                self.stdin.read(&mut b).unwrap();
+                break;
+            }
+        }
+
+        self.mode = Mode::Edit;
+        self.map = map;
+        self.x = cursor_x;
+        self.y = cursor_y;
+        self.follow();
+        self.draw();
+    }
+
+    /// Save the map to a file.
+    ///
+    /// Unless forced, this refuses to save a map the solver proves unsolvable. Returns true if the
+    /// map was saved.
+    fn save(&mut self, path: &str, force: bool) -> bool {
+        let verdict = solver::solve(&self.map, self.start_position());
+
+        if verdict == Verdict::Unsolvable && !force {
+            self.message("This map is unsolvable! Press F to save it anyway.");
+            return false;
+        }
+
+        let result = File::create(path).and_then(|mut file| file.write_all(&self.map.to_bytes()));
+        let saved = result.is_ok();
+        match (result, verdict) {
+            (Ok(()), Verdict::Solvable(moves)) => self.message(&format!("Saved to {} (solvable in {} moves).", path, moves)),
+            (Ok(()), Verdict::Unsolvable) => self.message(&format!("Saved to {} (unsolvable).", path)),
+            (Ok(()), Verdict::Unknown) => self.message(&format!("Saved to {} (too big to check it is solvable).", path)),
+            (Err(err), _) => self.message(&format!("Could not save to {}: {}", path, err)),
+        }
+        saved
+    }
+}
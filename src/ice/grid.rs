@@ -0,0 +1,286 @@
+//! The grid of tiles making up a map, and its text format.
+//!
+//! In the text format, every line is a row of tiles: `' '` is ice, `'@'` is the goal, `'*'` is
+//! the start (ice the player begins on), `'.'` is a target, `'$'` is a crate on ice, `'%'` is a
+//! crate on a target and anything else is a wall. Lines may end with either LF or CRLF, and may
+//! have different lengths.
+
+/// A tile of the map.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Tile {
+    /// Nothing. This is what lies outside of the map, or past the end of a short row.
+    Void,
+    /// A wall, printed as the given character.
+    Wall(u8),
+    /// Ice.
+    Ice,
+    /// The goal.
+    Goal,
+    /// The ice the player starts on.
+    Start,
+    /// Ice which must be covered by a crate to finish the level.
+    Target,
+}
+
+impl Tile {
+    /// Parse a tile from its character in the text format.
+    pub fn from_byte(c: u8) -> Tile {
+        match c {
+            b' ' => Tile::Ice,
+            b'@' => Tile::Goal,
+            b'*' => Tile::Start,
+            b'.' => Tile::Target,
+            c => Tile::Wall(c),
+        }
+    }
+
+    /// Get the character of the tile in the text format.
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Tile::Void => b'#',
+            Tile::Wall(c) => c,
+            Tile::Ice => b' ',
+            Tile::Goal => b'@',
+            Tile::Start => b'*',
+            Tile::Target => b'.',
+        }
+    }
+
+    /// Can the player and the crates slide over this tile?
+    pub fn is_ice(self) -> bool {
+        self == Tile::Ice || self == Tile::Start || self == Tile::Target
+    }
+}
+
+/// A rectangular grid of tiles, with the crates lying on them.
+#[derive(Clone)]
+pub struct Grid {
+    /// The width of the grid.
+    width: u16,
+    /// The height of the grid.
+    height: u16,
+    /// The tiles, row by row.
+    tiles: Vec<Tile>,
+    /// The positions of the crates.
+    crates: Vec<(u16, u16)>,
+}
+
+impl Grid {
+    /// Create a grid of a given size, filled with a tile.
+    pub fn new(width: u16, height: u16, tile: Tile) -> Grid {
+        Grid {
+            width: width,
+            height: height,
+            tiles: vec![tile; width as usize * height as usize],
+            crates: Vec::new(),
+        }
+    }
+
+    /// Parse a grid from the text format.
+    ///
+    /// The width is the length of the longest row, and shorter rows are padded with `Tile::Void`.
+    pub fn parse(text: &[u8]) -> Grid {
+        let mut rows: Vec<&[u8]> = text.split(|&c| c == b'\n')
+            .map(|row| if row.last() == Some(&b'\r') { &row[..row.len() - 1] } else { row })
+            .collect();
+
+        // The final newline does not start a new row.
+        if rows.last().map_or(false, |row| row.is_empty()) {
+            rows.pop();
+        }
+
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut grid = Grid::new(width as u16, rows.len() as u16, Tile::Void);
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &c) in row.iter().enumerate() {
+                let (x, y) = (x as u16, y as u16);
+
+                match c {
+                    b'$' => {
+                        grid.set(x, y, Tile::Ice);
+                        grid.crates.push((x, y));
+                    },
+                    b'%' => {
+                        grid.set(x, y, Tile::Target);
+                        grid.crates.push((x, y));
+                    },
+                    c => grid.set(x, y, Tile::from_byte(c)),
+                }
+            }
+        }
+
+        grid
+    }
+
+    /// Write the grid in the text format.
+    ///
+    /// Trailing `Tile::Void`s are left out of the rows.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        if self.width == 0 {
+            return vec![b'\n'; self.height as usize];
+        }
+
+        let mut text = Vec::with_capacity((self.width as usize + 1) * self.height as usize);
+
+        for (y, row) in self.tiles.chunks(self.width as usize).enumerate() {
+            let len = row.iter().rposition(|&tile| tile != Tile::Void).map_or(0, |x| x + 1);
+
+            for (x, &tile) in row[..len].iter().enumerate() {
+                text.push(match tile {
+                    Tile::Target if self.has_crate(x as u16, y as u16) => b'%',
+                    _ if self.has_crate(x as u16, y as u16) => b'$',
+                    tile => tile.to_byte(),
+                });
+            }
+            text.push(b'\n');
+        }
+
+        text
+    }
+
+    /// The width of the grid.
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// The height of the grid.
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Is (x, y) inside the grid?
+    pub fn contains(&self, x: u16, y: u16) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// Get the tile at (x, y).
+    ///
+    /// Everything outside of the grid is `Tile::Void`.
+    pub fn get(&self, x: u16, y: u16) -> Tile {
+        if self.contains(x, y) {
+            self.tiles[y as usize * self.width as usize + x as usize]
+        } else {
+            Tile::Void
+        }
+    }
+
+    /// Set the tile at (x, y). This does nothing outside of the grid.
+    pub fn set(&mut self, x: u16, y: u16, tile: Tile) {
+        if self.contains(x, y) {
+            self.tiles[y as usize * self.width as usize + x as usize] = tile;
+        }
+    }
+
+    /// The positions of the crates.
+    pub fn crates(&self) -> &[(u16, u16)] {
+        &self.crates
+    }
+
+    /// Is there a crate at (x, y)?
+    pub fn has_crate(&self, x: u16, y: u16) -> bool {
+        self.crates.contains(&(x, y))
+    }
+
+    /// Put or remove a crate at (x, y).
+    ///
+    /// The text format has no character for a crate on the start, so no crate is put there.
+    pub fn set_crate(&mut self, x: u16, y: u16, present: bool) {
+        self.crates.retain(|&pos| pos != (x, y));
+        if present && self.contains(x, y) && self.get(x, y) != Tile::Start {
+            self.crates.push((x, y));
+        }
+    }
+
+    /// Move the crate at `from` to `to`.
+    pub fn move_crate(&mut self, from: (u16, u16), to: (u16, u16)) {
+        for pos in &mut self.crates {
+            if *pos == from {
+                *pos = to;
+            }
+        }
+    }
+
+    /// Would the level be finished with crates at the given positions?
+    ///
+    /// A level is finished when all of its targets are covered by crates and, if it has a goal,
+    /// the player reached it.
+    pub fn is_finished(&self, crates: &[(u16, u16)], at_goal: bool) -> bool {
+        let covered = self.tiles.iter().enumerate()
+            .filter(|&(_, &tile)| tile == Tile::Target)
+            .all(|(i, _)| crates.contains(&((i % self.width as usize) as u16, (i / self.width as usize) as u16)));
+
+        covered && (at_goal || self.find(Tile::Goal).is_none())
+    }
+
+    /// Find the first position holding a given tile, row by row.
+    pub fn find(&self, tile: Tile) -> Option<(u16, u16)> {
+        self.tiles.iter().position(|&x| x == tile)
+            .map(|i| ((i % self.width as usize) as u16, (i / self.width as usize) as u16))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crlf_is_a_line_ending() {
+        let grid = Grid::parse(b"####\r\n#* #\r\n####\r\n");
+
+        assert_eq!((grid.width(), grid.height()), (4, 3));
+        assert_eq!(grid.get(3, 1), Tile::Wall(b'#'));
+        assert_eq!(grid.to_bytes(), b"####\n#* #\n####\n".to_vec());
+    }
+
+    #[test]
+    fn short_rows_are_padded_with_void() {
+        let grid = Grid::parse(b"#####\n#*\n#  @#\n###\n");
+
+        assert_eq!((grid.width(), grid.height()), (5, 4));
+        assert_eq!(grid.get(1, 1), Tile::Start);
+        assert_eq!(grid.get(2, 1), Tile::Void);
+        assert_eq!(grid.get(4, 3), Tile::Void);
+        assert!(!grid.get(4, 1).is_ice());
+    }
+
+    #[test]
+    fn maps_without_borders_end_in_void() {
+        let grid = Grid::parse(b"*  \n  @");
+
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.get(2, 1), Tile::Goal);
+        assert_eq!(grid.get(3, 0), Tile::Void);
+        assert_eq!(grid.get(0, 2), Tile::Void);
+    }
+
+    #[test]
+    fn outside_of_the_map_is_solid() {
+        let grid = Grid::parse(b"* \n  \n");
+
+        for &(x, y) in &[(2, 0), (0, 2), (2, 2), (u16::max_value(), 0), (0, u16::max_value())] {
+            assert_eq!(grid.get(x, y), Tile::Void);
+            assert!(!grid.get(x, y).is_ice());
+        }
+    }
+
+    #[test]
+    fn crates_round_trip() {
+        let text = b"######\n#*$ .#\n# %$ #\n######\n";
+        let mut grid = Grid::parse(text);
+
+        assert_eq!(grid.crates(), &[(2, 1), (2, 2), (3, 2)]);
+        assert_eq!(grid.get(2, 1), Tile::Ice);
+        assert_eq!(grid.get(2, 2), Tile::Target);
+        assert_eq!(grid.to_bytes(), text.to_vec());
+
+        // A crate on the start would lose the start when saved.
+        grid.set_crate(1, 1, true);
+        assert!(!grid.has_crate(1, 1));
+        assert_eq!(grid.to_bytes(), text.to_vec());
+
+        grid.set_crate(4, 1, true);
+        grid.set_crate(2, 1, false);
+        assert_eq!(grid.to_bytes(), b"######\n#*  %#\n# %$ #\n######\n".to_vec());
+    }
+}
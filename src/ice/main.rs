@@ -1,8 +1,18 @@
 extern crate termion;
 
-use termion::{clear, cursor, style};
+mod editor;
+mod grid;
+mod progress;
+mod solver;
+
+use grid::{Grid, Tile};
+use progress::Progress;
+use termion::{clear, color, cursor, style};
 use termion::raw::{IntoRawMode, RawTerminal};
+use std::cmp;
+use std::env;
 use std::io::{self, Write, Read};
+use std::process;
 use std::thread;
 use std::time;
 
@@ -11,8 +21,75 @@ const MAP_2: &'static [u8] = include_bytes!("map2.txt");
 const MAP_3: &'static [u8] = include_bytes!("map3.txt");
 const MAP_4: &'static [u8] = include_bytes!("map4.txt");
 const MAP_5: &'static [u8] = include_bytes!("map5.txt");
+const MAP_6: &'static [u8] = include_bytes!("map6.txt");
 const DONE: &'static [u8] = include_bytes!("done.txt");
 
+/// The maps of the levels, in order.
+const MAPS: [&'static [u8]; 6] = [MAP_1, MAP_2, MAP_3, MAP_4, MAP_5, MAP_6];
+
+/// The string printed for the player.
+const PLAYER: &'static str = "●";
+/// The string printed for the goal.
+const GOAL: &'static str = "⚑";
+/// The string printed for crates.
+const CRATE: &'static str = "▣";
+/// The string printed for targets.
+const TARGET: &'static str = "◦";
+/// The string printed for the start in the editor.
+const START_MARK: &'static str = "▲";
+/// The string printed in the status bar of the editor.
+const EDITOR_HELP: &'static str = "hjkl: move, #/space/@/*/$/.: wall/ice/goal/start/crate/target, t: test, S: save, q: quit";
+
+/// The default delay between two frames of a slide, in milliseconds.
+const DEFAULT_DELAY: u64 = 10;
+
+/// The help page.
+const HELP: &'static str = r#"
+ice ~ a pokemon-style ice sliding puzzle.
+
+rules:
+    Slide over the ice until something solid stops you, and reach the goal. Crates slide too:
+    run into one to push it. When a level has targets, the goal only opens once every target
+    is covered by a crate.
+
+flags:
+    -l | --level N ~ start directly at level N (it must be unlocked).
+    -d | --delay N ~ wait N milliseconds between two frames of a slide.
+    -e | --edit F  ~ edit the map stored in file F.
+    -h | --help    ~ this help page.
+
+controls:
+    ---level select-----
+    k | w     ~ up
+    j | s     ~ down
+    1-9       ~ pick level
+    enter     ~ play level
+    q         ~ quit
+    ---in game----------
+    h | a     ~ slide left
+    j | s     ~ slide down
+    k | w     ~ slide up
+    l | d     ~ slide right
+    r         ~ restart level
+    m         ~ level select
+    q         ~ quit
+    ---editor-----------
+    h | a     ~ cursor left
+    j | s     ~ cursor down
+    k | w     ~ cursor up
+    l | d     ~ cursor right
+    #         ~ paint wall
+    space     ~ paint ice
+    @         ~ paint goal
+    *         ~ paint start
+    $         ~ paint crate
+    .         ~ paint target
+    t         ~ test-play the map
+    S         ~ save (solvable maps only)
+    F         ~ force save
+    q         ~ quit
+"#;
+
 /// The game state.
 struct Game<R, W: Write> {
     /// The x coordinate.
@@ -23,12 +100,33 @@ struct Game<R, W: Write> {
     stdout: W,
     /// Standard input.
     stdin: R,
-    /// The width of the map.
-    width: usize,
+    /// The x coordinate of the top-left corner of the viewport.
+    scroll_x: u16,
+    /// The y coordinate of the top-left corner of the viewport.
+    scroll_y: u16,
+    /// The delay between two frames of a slide, in milliseconds.
+    delay: u64,
     /// The map.
-    map: &'static [u8],
+    map: Grid,
+    /// Whether the map is played or edited.
+    mode: Mode,
     /// Current level.
-    level: u8,
+    level: usize,
+    /// The number of moves made in the current level.
+    moves: u32,
+    /// The completed levels and their best move counts.
+    progress: Progress,
+}
+
+/// What the game is currently doing with the map.
+#[derive(Copy, Clone, PartialEq)]
+enum Mode {
+    /// Playing a level.
+    Play,
+    /// Editing a map.
+    Edit,
+    /// Test-playing a map from the editor.
+    Test,
 }
 
 /// A direction.
@@ -44,38 +142,134 @@ enum Direction {
     Right,
 }
 
+/// All the directions.
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+impl Direction {
+    /// Get the position one step away from (x, y) in this direction.
+    ///
+    /// Returns `None` if it would go below zero.
+    fn step(self, x: u16, y: u16) -> Option<(u16, u16)> {
+        match self {
+            Direction::Right => Some((x + 1, y)),
+            Direction::Left => x.checked_sub(1).map(|x| (x, y)),
+            Direction::Down => Some((x, y + 1)),
+            Direction::Up => y.checked_sub(1).map(|y| (x, y)),
+        }
+    }
+}
+
 impl<R: Read, W: Write> Game<R, W> {
     /// Construct a new game state.
-    fn new(stdin: R, stdout: W) -> Game<R, RawTerminal<W>> {
+    fn new(stdin: R, stdout: W, delay: u64) -> Game<R, RawTerminal<W>> {
         Game {
             x: 1,
             y: 1,
             stdout: stdout.into_raw_mode().unwrap(),
             stdin: stdin,
-            width: 1,
-            map: MAP_1,
+            scroll_x: 0,
+            scroll_y: 0,
+            delay: delay,
+            map: Grid::parse(MAP_1),
+            mode: Mode::Play,
             level: 0,
+            moves: 0,
+            progress: Progress::load(MAPS.len()),
         }
     }
 
-    /// Start the game loop.
+    /// Start the game.
     ///
-    /// This will listen to events and do the appropriate actions.
-    fn start(&mut self) {
-        self.init();
+    /// This starts at the given level if any, and at the level select menu otherwise.
+    fn start(&mut self, level: Option<usize>) {
+        let mut next = level;
 
         loop {
-            // Read a single byte from stdin.
+            let level = match next.take() {
+                Some(level) => level,
+                None => match self.menu() {
+                    Some(level) => level,
+                    None => return,
+                },
+            };
+
+            self.load(level);
+            if !self.play() {
+                return;
+            }
+        }
+    }
+
+    /// Show the level select menu.
+    ///
+    /// Returns the chosen level, or `None` if the player quit.
+    fn menu(&mut self) -> Option<usize> {
+        let mut selected = self.level;
+
+        loop {
+            write!(self.stdout, "{}{}{}ICE ~ LEVEL SELECT{}\n\n\r",
+                   clear::All, cursor::Goto(1, 1), style::Bold, style::Reset).unwrap();
+
+            for level in 0..self.progress.levels() {
+                let marker = if level == selected { ">" } else { " " };
+                let status = if !self.progress.is_unlocked(level) {
+                    "locked".to_string()
+                } else if let Some(best) = self.progress.best(level) {
+                    format!("done, best {} moves", best)
+                } else {
+                    "unsolved".to_string()
+                };
+
+                write!(self.stdout, "{} LEVEL {:<3} {}\n\r", marker, level + 1, status).unwrap();
+            }
+            write!(self.stdout, "\n\rj/k: select, enter: play, q: quit").unwrap();
+            self.stdout.flush().unwrap();
+
             let mut b = [0];
             self.stdin.read(&mut b).unwrap();
 
             match b[0] {
+                b'k' | b'w' if selected > 0 => selected -= 1,
+                b'j' | b's' if selected + 1 < self.progress.levels() => selected += 1,
+                b'1'...b'9' => {
+                    let level = (b[0] - b'1') as usize;
+                    if self.progress.is_unlocked(level) {
+                        return Some(level);
+                    }
+                },
+                b'\r' | b'\n' | b' ' if self.progress.is_unlocked(selected) => return Some(selected),
+                b'q' => return None,
+                _ => {},
+            }
+        }
+    }
+
+    /// Play the current level, and the following ones as they are completed.
+    ///
+    /// Returns true if the player went back to the level select menu, and false if they quit.
+    fn play(&mut self) -> bool {
+        loop {
+            // Read a single byte from stdin.
+            let mut b = [0];
+            self.stdin.read(&mut b).unwrap();
+
+            let reached = match b[0] {
                 b'h' | b'a' => self.slide(Direction::Left),
                 b'j' | b's' => self.slide(Direction::Down),
                 b'k' | b'w' => self.slide(Direction::Up),
                 b'l' | b'd' => self.slide(Direction::Right),
-                b'q' => return,
-                _ => {},
+                b'r' => {
+                    let level = self.level;
+                    self.load(level);
+                    false
+                },
+                b'm' => return true,
+                b'q' => return false,
+                _ => false,
+            };
+
+            if reached && !self.done() {
+                return true;
             }
 
             self.stdout.flush().unwrap();
@@ -84,114 +278,372 @@ impl<R: Read, W: Write> Game<R, W> {
 
     /// Initialize the level.
     fn init(&mut self) {
-        write!(self.stdout, "{}{}", clear::All, cursor::Goto(1, 1)).unwrap();
+        self.scroll_x = 0;
+        self.scroll_y = 0;
+
+        let (x, y) = self.start_position();
+        self.x = x;
+        self.y = y;
 
-        let mut width_counted = false;
+        self.follow();
+        self.draw();
+    }
 
-        for &i in self.map {
+    /// Get the position of the start of the map.
+    ///
+    /// Maps without a start begin in the top-left corner.
+    fn start_position(&self) -> (u16, u16) {
+        self.map.find(Tile::Start).unwrap_or((1, 1))
+    }
+
+    /// Print a text screen as is.
+    fn show(&mut self, text: &[u8]) {
+        write!(self.stdout, "{}{}", clear::All, cursor::Goto(1, 1)).unwrap();
+
+        for &i in text {
             if i == b'\n' {
-                width_counted = true;
                 self.stdout.write(b"\n\r").unwrap();
-                if !width_counted {
-                    width_counted = true;
-                }
             } else {
                 self.stdout.write(&[i]).unwrap();
             }
-            if !width_counted {
-                self.width += 1;
+        }
+        self.stdout.flush().unwrap();
+    }
+
+    /// Get the size of the viewport.
+    ///
+    /// This is the part of the map fitting in the terminal, leaving a line for the status bar.
+    fn view_size(&self) -> (u16, u16) {
+        let (term_width, term_height) = termion::terminal_size().unwrap_or((80, 24));
+
+        (cmp::min(cmp::max(term_width, 1), self.map.width()),
+         cmp::min(cmp::max(term_height.saturating_sub(1), 1), self.map.height()))
+    }
+
+    /// Scroll the viewport such that the player stays away from its edges.
+    ///
+    /// Returns true if the viewport moved.
+    fn follow(&mut self) -> bool {
+        let (view_width, view_height) = self.view_size();
+        let scroll_x = follow_axis(self.scroll_x, self.x, view_width, self.map.width());
+        let scroll_y = follow_axis(self.scroll_y, self.y, view_height, self.map.height());
+        let moved = (scroll_x, scroll_y) != (self.scroll_x, self.scroll_y);
+
+        self.scroll_x = scroll_x;
+        self.scroll_y = scroll_y;
+        moved
+    }
+
+    /// Draw the visible part of the map, the player and the status bar.
+    fn draw(&mut self) {
+        write!(self.stdout, "{}{}", clear::All, cursor::Hide).unwrap();
+
+        let (view_width, view_height) = self.view_size();
+        for y in self.scroll_y..self.scroll_y + view_height {
+            for x in self.scroll_x..self.scroll_x + view_width {
+                self.draw_tile(x, y);
             }
         }
+
         self.update();
     }
 
-    /// Get the position of the next step.
-    ///
-    /// This will calculate the position of the step in a given direction.
-    fn next(&mut self, dir: Direction) -> (u16, u16) {
-        let mut new_x = self.x;
-        let mut new_y = self.y;
+    /// Draw the tile at a given (x, y) of the map, if it is in the viewport.
+    fn draw_tile(&mut self, x: u16, y: u16) {
+        let (view_width, view_height) = self.view_size();
+        if x < self.scroll_x || y < self.scroll_y || x >= self.scroll_x + view_width || y >= self.scroll_y + view_height {
+            return;
+        }
+
+        write!(self.stdout, "{}", cursor::Goto(x - self.scroll_x + 1, y - self.scroll_y + 1)).unwrap();
 
-        match dir {
-            Direction::Right => new_x += 1,
-            Direction::Left => new_x -= 1,
-            Direction::Down => new_y += 1,
-            Direction::Up => new_y -= 1,
+        if self.map.has_crate(x, y) {
+            if self.map.get(x, y) == Tile::Target {
+                write!(self.stdout, "{}{}{}{}", color::Bg(color::LightCyan), color::Fg(color::Green), CRATE, style::Reset)
+            } else {
+                write!(self.stdout, "{}{}{}{}", color::Bg(color::LightCyan), color::Fg(color::Yellow), CRATE, style::Reset)
+            }.unwrap();
+            return;
         }
 
-        (new_x, new_y)
+        match self.map.get(x, y) {
+            Tile::Void => write!(self.stdout, " "),
+            Tile::Wall(c) => write!(self.stdout, "{}{}{}", color::Bg(color::Blue), color::Fg(color::LightWhite), c as char),
+            Tile::Start if self.mode == Mode::Edit => write!(self.stdout, "{}{}{}", color::Bg(color::LightCyan), color::Fg(color::Black), START_MARK),
+            Tile::Ice | Tile::Start => write!(self.stdout, "{} ", color::Bg(color::LightCyan)),
+            Tile::Target => write!(self.stdout, "{}{}{}", color::Bg(color::LightCyan), color::Fg(color::Red), TARGET),
+            Tile::Goal => write!(self.stdout, "{}{}{}", color::Bg(color::LightCyan), color::Fg(color::Red), GOAL),
+        }.unwrap();
+
+        write!(self.stdout, "{}", style::Reset).unwrap();
     }
 
-    /// Get the character of a given (x, y).
-    fn get(&mut self, x: u16, y: u16) -> u8 {
-         self.map[y as usize * self.width + x as usize]
+    /// Get the position of the next step.
+    ///
+    /// This will calculate the position of the step in a given direction, or `None` if it is off
+    /// the left or top edge.
+    fn next(&self, dir: Direction) -> Option<(u16, u16)> {
+        dir.step(self.x, self.y)
+    }
+
+    /// Get the tile at a given (x, y).
+    ///
+    /// Coordinates outside of the map give `Tile::Void`, which is solid.
+    fn get(&self, x: u16, y: u16) -> Tile {
+        self.map.get(x, y)
     }
 
-    /// Move the cursor to the player position.
+    /// Draw the player at its position, and the status bar.
+    ///
+    /// In the editor, the terminal cursor is shown at the position of the editor cursor instead.
     fn update(&mut self) {
-        write!(self.stdout, "{}", cursor::Goto(self.x + 1, self.y + 1)).unwrap();
+        let (_, view_height) = self.view_size();
+        write!(self.stdout, "{}{}", cursor::Goto(1, view_height + 1), clear::CurrentLine).unwrap();
+
+        match self.mode {
+            Mode::Play => write!(self.stdout, "{}LEVEL {}{} ~ moves: {}", style::Bold, self.level + 1, style::Reset, self.moves),
+            Mode::Test => write!(self.stdout, "{}TEST{} ~ moves: {} ~ q: back to editing", style::Bold, style::Reset, self.moves),
+            Mode::Edit => write!(self.stdout, "{}EDIT{} ~ {}", style::Bold, style::Reset, EDITOR_HELP),
+        }.unwrap();
+
+        let goto = cursor::Goto(self.x - self.scroll_x + 1, self.y - self.scroll_y + 1);
+        if self.mode == Mode::Edit {
+            write!(self.stdout, "{}{}", goto, cursor::Show).unwrap();
+        } else {
+            write!(self.stdout, "{}{}{}{}{}", goto, color::Bg(color::LightCyan), color::Fg(color::Black), PLAYER, style::Reset).unwrap();
+        }
         self.stdout.flush().unwrap();
     }
 
-    /// The level is done. Go to the next level.
-    fn done(&mut self) {
-        self.level += 1;
-        self.width = 1;
-        self.x = 1;
-        self.y = 1;
+    /// Show a message in the status bar, until the next update.
+    fn message(&mut self, msg: &str) {
+        let (_, view_height) = self.view_size();
+        write!(self.stdout, "{}{}{}", cursor::Goto(1, view_height + 1), clear::CurrentLine, msg).unwrap();
+
+        if self.mode == Mode::Edit {
+            write!(self.stdout, "{}", cursor::Goto(self.x - self.scroll_x + 1, self.y - self.scroll_y + 1)).unwrap();
+        }
+        self.stdout.flush().unwrap();
+    }
+
+    /// Move the player to a given (x, y), redrawing what changed.
+    fn step(&mut self, x: u16, y: u16) {
+        let (old_x, old_y) = (self.x, self.y);
+        self.x = x;
+        self.y = y;
+
+        if self.follow() {
+            self.draw();
+        } else {
+            self.draw_tile(old_x, old_y);
+            self.update();
+        }
+    }
 
-        let level = self.level;
-        self.map = self.get_map(level);
+    /// Load a level and draw it.
+    fn load(&mut self, level: usize) {
+        self.level = level;
+        self.moves = 0;
+        self.map = Grid::parse(MAPS[level]);
         self.init();
     }
 
-    /// Get the map of the level.
-    fn get_map(&mut self, level: u8) -> &'static [u8] {
-        match level {
-            0 => MAP_1,
-            1 => MAP_2,
-            2 => MAP_3,
-            3 => MAP_4,
-            4 => MAP_5,
-            _ => DONE,
+    /// The level is done. Record the progress and go to the next level.
+    ///
+    /// Returns false if this was the last level, in which case the final screen is shown until a
+    /// key is pressed.
+    fn done(&mut self) -> bool {
+        let (level, moves) = (self.level, self.moves);
+        if self.progress.record(level, moves) {
+            // Failing to save the progress should not interrupt the game.
+            let _ = self.progress.save();
+        }
+
+        if level + 1 < MAPS.len() {
+            self.load(level + 1);
+            true
+        } else {
+            self.show(DONE);
+
+            let mut b = [0];
+            self.stdin.read(&mut b).unwrap();
+            false
         }
     }
 
     /// Slide the character over the ices until a solid block is reached.
-    fn slide(&mut self, dir: Direction) {
+    ///
+    /// If the player runs into a crate, it stops and the crate slides on in its place.
+    ///
+    /// Returns true if the level was finished.
+    fn slide(&mut self, dir: Direction) -> bool {
+        let mut first = true;
+
         loop {
-            let (x, y) = self.next(dir);
+            let (x, y) = match self.next(dir) {
+                Some(pos) => pos,
+                None => return false,
+            };
 
-            match self.get(x, y) {
-                b'@' => {
-                    self.done();
-                    break;
-                },
-                b' ' => {
-                    self.x = x;
-                    self.y = y;
+            if self.map.has_crate(x, y) {
+                let pushed = self.push(x, y, dir);
+                if first && pushed {
+                    self.moves += 1;
                     self.update();
+                }
+
+                return pushed && self.map.is_finished(self.map.crates(), false);
+            }
+
+            // The goal is only open once all the targets are covered.
+            let tile = self.get(x, y);
+            let open = tile == Tile::Goal && self.map.is_finished(self.map.crates(), true);
+
+            // Bumping into a wall does not count as a move.
+            if first && (tile.is_ice() || open) {
+                self.moves += 1;
+            }
+            first = false;
+
+            match tile {
+                _ if open => return true,
+                tile if tile.is_ice() => {
+                    self.step(x, y);
 
-                    thread::sleep(time::Duration::from_millis(10));
+                    thread::sleep(time::Duration::from_millis(self.delay));
                 },
-                _ => break,
+                _ => return false,
             }
         }
     }
+
+    /// Push the crate at (x, y), which slides until it hits a solid tile or another crate.
+    ///
+    /// Returns true if the crate moved.
+    fn push(&mut self, x: u16, y: u16, dir: Direction) -> bool {
+        let (mut crate_x, mut crate_y) = (x, y);
+
+        while let Some((next_x, next_y)) = dir.step(crate_x, crate_y) {
+            if !self.get(next_x, next_y).is_ice() || self.map.has_crate(next_x, next_y) {
+                break;
+            }
+
+            self.map.move_crate((crate_x, crate_y), (next_x, next_y));
+            self.draw_tile(crate_x, crate_y);
+            self.draw_tile(next_x, next_y);
+            self.update();
+
+            crate_x = next_x;
+            crate_y = next_y;
+
+            thread::sleep(time::Duration::from_millis(self.delay));
+        }
+
+        (crate_x, crate_y) != (x, y)
+    }
 }
 
 impl<R, W: Write> Drop for Game<R, W> {
     fn drop(&mut self) {
         // When done, restore the defaults to avoid messing with the terminal.
-        write!(self.stdout, "{}{}{}", clear::All, style::Reset, cursor::Goto(1, 1)).unwrap();
+        write!(self.stdout, "{}{}{}{}", clear::All, style::Reset, cursor::Goto(1, 1), cursor::Show).unwrap();
     }
 }
 
+/// Get the new scroll of a viewport along one axis, such that `pos` stays away from its edges.
+///
+/// `view` is the length of the viewport and `size` the length of the map along this axis.
+fn follow_axis(scroll: u16, pos: u16, view: u16, size: u16) -> u16 {
+    let margin = view / 4;
+
+    let scroll = if pos < scroll + margin {
+        pos.saturating_sub(margin)
+    } else if pos + margin >= scroll + view {
+        pos + margin + 1 - view
+    } else {
+        scroll
+    };
+
+    cmp::min(scroll, size.saturating_sub(view))
+}
+
 fn main() {
+    let mut args = env::args().skip(1);
+    let mut level = None;
+    let mut delay = DEFAULT_DELAY;
+    let mut edit = None;
+
     let stdin = io::stdin();
     let stdout = io::stdout();
+    let stderr = io::stderr();
+    let mut stderr = stderr.lock();
+
+    loop {
+        // Read the arguments.
+        let arg = if let Some(x) = args.next() {
+            x
+        } else {
+            break;
+        };
 
-    let mut game = Game::new(stdin.lock(), stdout.lock());
+        match arg.as_str() {
+            "-l" | "--level" => {
+                let n: usize = args.next().unwrap_or_else(|| {
+                    stderr.write(b"no level given.\n").unwrap();
+                    stderr.flush().unwrap();
+                    process::exit(1);
+                }).parse().unwrap_or_else(|_| {
+                    stderr.write(b"invalid integer given.\n").unwrap();
+                    stderr.flush().unwrap();
+                    process::exit(1);
+                });
 
-    game.start();
+                if n == 0 || n > MAPS.len() {
+                    writeln!(stderr, "there is no level {} (levels go from 1 to {}).", n, MAPS.len()).unwrap();
+                    process::exit(1);
+                }
+                if !Progress::load(MAPS.len()).is_unlocked(n - 1) {
+                    writeln!(stderr, "level {} is locked.", n).unwrap();
+                    process::exit(1);
+                }
+                level = Some(n - 1);
+            },
+            "-d" | "--delay" => {
+                delay = args.next().unwrap_or_else(|| {
+                    stderr.write(b"no delay given.\n").unwrap();
+                    stderr.flush().unwrap();
+                    process::exit(1);
+                }).parse().unwrap_or_else(|_| {
+                    stderr.write(b"invalid integer given.\n").unwrap();
+                    stderr.flush().unwrap();
+                    process::exit(1);
+                });
+            },
+            "-e" | "--edit" => {
+                edit = Some(args.next().unwrap_or_else(|| {
+                    stderr.write(b"no map file given.\n").unwrap();
+                    stderr.flush().unwrap();
+                    process::exit(1);
+                }));
+            },
+            "-h" | "--help" => {
+                // Print the help page.
+                stdout.lock().write(HELP.as_bytes()).unwrap();
+                process::exit(0);
+            },
+            _ => {
+                stderr.write(b"Unknown argument.\n").unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            }
+        }
+    }
+
+    let mut game = Game::new(stdin.lock(), stdout.lock(), delay);
+
+    if let Some(path) = edit {
+        game.edit(&path);
+    } else {
+        game.start(level);
+    }
 }
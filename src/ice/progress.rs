@@ -0,0 +1,96 @@
+//! Persistent level progress.
+//!
+//! The progress file holds one line per completed level, made of the (1-based) level number
+//! followed by the best move count, e.g. `3 17`.
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+/// The name of the progress file, placed in the home directory.
+const FILE_NAME: &'static str = ".ice_progress";
+
+/// The player's progress through the levels.
+pub struct Progress {
+    /// The best move count of each level, or `None` if the level was never completed.
+    best: Vec<Option<u32>>,
+    /// Where the progress is stored.
+    path: PathBuf,
+}
+
+impl Progress {
+    /// Load the progress of `levels` levels from the progress file.
+    ///
+    /// A missing or malformed file results in no level being completed.
+    pub fn load(levels: usize) -> Progress {
+        let path = env::var_os("HOME").map(PathBuf::from).unwrap_or_else(PathBuf::new).join(FILE_NAME);
+        let mut best = vec![None; levels];
+
+        if let Ok(file) = File::open(&path) {
+            for line in BufReader::new(file).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                let mut words = line.split_whitespace();
+                let level = words.next().and_then(|x| x.parse::<usize>().ok());
+                let moves = words.next().and_then(|x| x.parse::<u32>().ok());
+
+                if let (Some(level), Some(moves)) = (level, moves) {
+                    if level >= 1 && level <= levels {
+                        best[level - 1] = Some(moves);
+                    }
+                }
+            }
+        }
+
+        Progress {
+            best: best,
+            path: path,
+        }
+    }
+
+    /// Write the progress back to the progress file.
+    pub fn save(&self) -> io::Result<()> {
+        let mut file = try!(File::create(&self.path));
+
+        for (level, best) in self.best.iter().enumerate() {
+            if let Some(moves) = *best {
+                try!(writeln!(file, "{} {}", level + 1, moves));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The number of levels.
+    pub fn levels(&self) -> usize {
+        self.best.len()
+    }
+
+    /// Get the best move count of a level, if it has been completed.
+    pub fn best(&self, level: usize) -> Option<u32> {
+        self.best.get(level).and_then(|&x| x)
+    }
+
+    /// Is this level playable?
+    ///
+    /// The first level is always unlocked, and completing a level unlocks the next one.
+    pub fn is_unlocked(&self, level: usize) -> bool {
+        level < self.levels() && (level == 0 || self.best(level - 1).is_some())
+    }
+
+    /// Record that a level was completed in a given number of moves.
+    ///
+    /// Returns true if this is a new best.
+    pub fn record(&mut self, level: usize, moves: u32) -> bool {
+        match self.best(level) {
+            Some(best) if best <= moves => false,
+            _ => {
+                self.best[level] = Some(moves);
+                true
+            },
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! A solver telling whether (and how fast) a map can be finished.
+
+use std::collections::{HashSet, VecDeque};
+
+use super::{Direction, DIRECTIONS};
+use grid::{Grid, Tile};
+
+/// The most positions explored before giving up.
+const MAX_STATES: usize = 1000000;
+
+/// What the solver found out about a map.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Verdict {
+    /// The map can be finished in this many moves, at best.
+    Solvable(u32),
+    /// The map cannot be finished.
+    Unsolvable,
+    /// There were too many positions to explore to conclude.
+    Unknown,
+}
+
+/// A position of the search: where the player is, and where the crates are (sorted).
+type State = ((u16, u16), Vec<(u16, u16)>);
+
+/// Slide the player in a direction, following the same rules as `Game::slide`.
+///
+/// Returns the new position and whether the level is finished, or `None` if nothing moved.
+fn slide(map: &Grid, player: (u16, u16), crates: &[(u16, u16)], dir: Direction) -> Option<(State, bool)> {
+    let (mut x, mut y) = player;
+
+    while let Some((next_x, next_y)) = dir.step(x, y) {
+        if crates.contains(&(next_x, next_y)) {
+            // The player stops, and the crate slides on.
+            let (mut crate_x, mut crate_y) = (next_x, next_y);
+            while let Some((to_x, to_y)) = dir.step(crate_x, crate_y) {
+                if !map.get(to_x, to_y).is_ice() || crates.contains(&(to_x, to_y)) {
+                    break;
+                }
+                crate_x = to_x;
+                crate_y = to_y;
+            }
+
+            if (crate_x, crate_y) != (next_x, next_y) {
+                let mut crates: Vec<_> = crates.iter()
+                    .map(|&pos| if pos == (next_x, next_y) { (crate_x, crate_y) } else { pos })
+                    .collect();
+                crates.sort();
+
+                let finished = map.is_finished(&crates, false);
+                return Some((((x, y), crates), finished));
+            }
+            break;
+        }
+
+        let tile = map.get(next_x, next_y);
+        if tile == Tile::Goal && map.is_finished(crates, true) {
+            return Some((((next_x, next_y), crates.to_vec()), true));
+        } else if !tile.is_ice() {
+            break;
+        }
+
+        x = next_x;
+        y = next_y;
+    }
+
+    if (x, y) == player {
+        None
+    } else {
+        Some((((x, y), crates.to_vec()), false))
+    }
+}
+
+/// Find the least number of moves needed to finish a map, the player beginning at `start`.
+pub fn solve(map: &Grid, start: (u16, u16)) -> Verdict {
+    let mut crates = map.crates().to_vec();
+    crates.sort();
+
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    seen.insert((start, crates.clone()));
+    queue.push_back(((start, crates), 0));
+
+    // Breadth-first search over the positions where a move can stop.
+    while let Some(((player, crates), moves)) = queue.pop_front() {
+        for &dir in &DIRECTIONS {
+            match slide(map, player, &crates, dir) {
+                Some((_, true)) => return Verdict::Solvable(moves + 1),
+                Some((state, false)) => {
+                    if seen.insert(state.clone()) {
+                        if seen.len() > MAX_STATES {
+                            return Verdict::Unknown;
+                        }
+                        queue.push_back((state, moves + 1));
+                    }
+                },
+                None => {},
+            }
+        }
+    }
+
+    Verdict::Unsolvable
+}
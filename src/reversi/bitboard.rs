@@ -0,0 +1,254 @@
+//! Bitboard primitives: a set of cells is a `Bits`, where cell `(row, col)` is bit `row * STRIDE + col`.
+//! Rows are `STRIDE` bits apart whatever the size of the board, so that any board up to `MAX_BOARD_SIZE` fits
+//! and shifting a set one step along a direction is the same operation for all sizes.
+//! Legal moves and flips are computed for a whole set of cells at once by shifting it along each direction,
+//! which is much faster than walking the board cell by cell.
+
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+use board::{Coord, Direction, DIRECTIONS, MAX_BOARD_SIZE};
+
+/// Distance between the bits of two cells one above the other.
+pub const STRIDE: usize = MAX_BOARD_SIZE;
+
+/// Number of 64-bit words making up a set.
+const WORDS: usize = STRIDE * STRIDE / 64;
+
+/// All cells but the first column: what is left after shifting East (to be repeated in every word).
+const NOT_FIRST_COL: u64 = 0xfffe_fffe_fffe_fffe;
+
+/// All cells but the last column: what is left after shifting West (to be repeated in every word).
+const NOT_LAST_COL: u64 = 0x7fff_7fff_7fff_7fff;
+
+/// A set of cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Bits([u64; WORDS]);
+
+impl Bits {
+    /// The empty set.
+    pub fn empty() -> Bits {
+        Bits([0; WORDS])
+    }
+
+    /// The set made of the single cell with the given index.
+    pub fn single(index: u32) -> Bits {
+        let mut bits = Bits::empty();
+        bits.0[index as usize / 64] = 1 << (index % 64);
+        bits
+    }
+
+    /// The set of all the cells of a board of the given size.
+    pub fn board(size: usize) -> Bits {
+        let row = (1u64 << size) - 1;
+        let mut bits = Bits::empty();
+        for r in 0..size {
+            bits.0[r * STRIDE / 64] |= row << (r * STRIDE % 64);
+        }
+        bits
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    pub fn count_ones(&self) -> u32 {
+        self.0.iter().map(|word| word.count_ones()).sum()
+    }
+
+    /// The index of the first cell of the set, if it is not empty.
+    pub fn first(&self) -> Option<u32> {
+        self.0.iter().position(|&word| word != 0)
+            .map(|i| i as u32 * 64 + self.0[i].trailing_zeros())
+    }
+
+    /// Returns whether the set holds any cell of another set.
+    pub fn intersects(&self, other: Bits) -> bool {
+        !(*self & other).is_empty()
+    }
+
+    /// The words making up the set.
+    pub fn words(&self) -> [u64; WORDS] {
+        self.0
+    }
+
+    /// The indexes of the cells of the set, in increasing order.
+    pub fn indexes(&self) -> Vec<u32> {
+        let mut indexes = Vec::with_capacity(self.count_ones() as usize);
+        for (i, &word) in self.0.iter().enumerate() {
+            let mut word = word;
+            while word != 0 {
+                indexes.push(i as u32 * 64 + word.trailing_zeros());
+                word &= word - 1;
+            }
+        }
+        indexes
+    }
+
+    /// Moves every bit towards higher indexes by `n` (less than 64).
+    fn shl(self, n: u32) -> Bits {
+        let mut bits = Bits::empty();
+        for i in 0..WORDS {
+            bits.0[i] = self.0[i] << n;
+            if i > 0 {
+                bits.0[i] |= self.0[i - 1] >> (64 - n);
+            }
+        }
+        bits
+    }
+
+    /// Moves every bit towards lower indexes by `n` (less than 64).
+    fn shr(self, n: u32) -> Bits {
+        let mut bits = Bits::empty();
+        for i in 0..WORDS {
+            bits.0[i] = self.0[i] >> n;
+            if i + 1 < WORDS {
+                bits.0[i] |= self.0[i + 1] << (64 - n);
+            }
+        }
+        bits
+    }
+
+    /// Keeps only the bits of a mask repeated in every word.
+    fn mask_words(mut self, mask: u64) -> Bits {
+        for word in &mut self.0 {
+            *word &= mask;
+        }
+        self
+    }
+}
+
+impl BitAnd for Bits {
+    type Output = Bits;
+    fn bitand(mut self, other: Bits) -> Bits {
+        self &= other;
+        self
+    }
+}
+
+impl BitOr for Bits {
+    type Output = Bits;
+    fn bitor(mut self, other: Bits) -> Bits {
+        self |= other;
+        self
+    }
+}
+
+impl BitXor for Bits {
+    type Output = Bits;
+    fn bitxor(mut self, other: Bits) -> Bits {
+        self ^= other;
+        self
+    }
+}
+
+impl Not for Bits {
+    type Output = Bits;
+    fn not(mut self) -> Bits {
+        for word in &mut self.0 {
+            *word = !*word;
+        }
+        self
+    }
+}
+
+impl BitAndAssign for Bits {
+    fn bitand_assign(&mut self, other: Bits) {
+        for (word, other) in self.0.iter_mut().zip(other.0.iter()) {
+            *word &= *other;
+        }
+    }
+}
+
+impl BitOrAssign for Bits {
+    fn bitor_assign(&mut self, other: Bits) {
+        for (word, other) in self.0.iter_mut().zip(other.0.iter()) {
+            *word |= *other;
+        }
+    }
+}
+
+impl BitXorAssign for Bits {
+    fn bitxor_assign(&mut self, other: Bits) {
+        for (word, other) in self.0.iter_mut().zip(other.0.iter()) {
+            *word ^= *other;
+        }
+    }
+}
+
+/// The index of a cell, i.e. the position of its bit.
+pub fn index(coord: Coord) -> u32 {
+    (coord.get_row() * STRIDE + coord.get_col()) as u32
+}
+
+/// The set made of one single cell.
+pub fn from_coord(coord: Coord) -> Bits {
+    Bits::single(index(coord))
+}
+
+/// The coordinates of the cell given by an index, i.e. by the position of its bit.
+pub fn to_coord(index: u32) -> Coord {
+    Coord::new(index as usize / STRIDE, index as usize % STRIDE)
+}
+
+/// Iterates over the coordinates of the cells in a set, row by row.
+pub fn coords(bits: Bits) -> Vec<Coord> {
+    bits.indexes().into_iter().map(to_coord).collect()
+}
+
+/// Moves every cell in the set one step along a direction.
+/// Cells stepping out of the largest board are lost, but those stepping out of a smaller one have to be masked off.
+pub fn shift(bits: Bits, dir: Direction) -> Bits {
+    let stride = STRIDE as u32;
+    match dir {
+        Direction::North => bits.shr(stride),
+        Direction::NE    => bits.shr(stride - 1).mask_words(NOT_FIRST_COL),
+        Direction::East  => bits.shl(1).mask_words(NOT_FIRST_COL),
+        Direction::SE    => bits.shl(stride + 1).mask_words(NOT_FIRST_COL),
+        Direction::South => bits.shl(stride),
+        Direction::SW    => bits.shl(stride - 1).mask_words(NOT_LAST_COL),
+        Direction::West  => bits.shr(1).mask_words(NOT_LAST_COL),
+        Direction::NW    => bits.shr(stride + 1).mask_words(NOT_LAST_COL),
+    }
+}
+
+/// Returns the set of legal moves for the player owning `own`, against the opponent owning `opp`,
+/// on a board made of the cells in `board`.
+pub fn legal_moves(own: Bits, opp: Bits, board: Bits) -> Bits {
+    let empty = !(own | opp) & board;
+    let mut moves = Bits::empty();
+
+    for &dir in &DIRECTIONS {
+        // Opponent's disks adjacent to ours, then runs of them
+        let mut run = shift(own, dir) & opp;
+        loop {
+            let longer = run | (shift(run, dir) & opp);
+            if longer == run {
+                break;
+            }
+            run = longer;
+        }
+        moves |= shift(run, dir) & empty;
+    }
+
+    moves
+}
+
+/// Returns the set of the opponent's disks flipped by playing `mv` (a single cell).
+/// It is empty if and only if the move is illegal.
+pub fn flips(own: Bits, opp: Bits, mv: Bits) -> Bits {
+    let mut flipped = Bits::empty();
+
+    for &dir in &DIRECTIONS {
+        let mut run = Bits::empty();
+        let mut next = shift(mv, dir);
+        while next.intersects(opp) {
+            run |= next;
+            next = shift(next, dir);
+        }
+        // The run is eaten only if it is closed by one of our disks
+        if next.intersects(own) {
+            flipped |= run;
+        }
+    }
+
+    flipped
+}
@@ -0,0 +1,460 @@
+//! Implementation of a 2D board (and of its constituing elements) with coordinates and iterators.
+
+use std::fmt;
+use std::str::FromStr;
+use {ReversiError, Result, Side};
+use bitboard::{self, Bits};
+
+/// The number of cells per side of the standard board.
+pub const DEFAULT_BOARD_SIZE: usize = 8;
+
+/// The smallest number of cells per side of a board.
+pub const MIN_BOARD_SIZE: usize = 4;
+
+/// The largest number of cells per side of a board.
+pub const MAX_BOARD_SIZE: usize = 16;
+
+/// Returns an error unless the size is even and between `MIN_BOARD_SIZE` and `MAX_BOARD_SIZE`.
+pub fn check_size(size: usize) -> Result<()> {
+    if size < MIN_BOARD_SIZE || size > MAX_BOARD_SIZE || size % 2 != 0 {
+        Err(ReversiError::InvalidBoardSize(size))
+    } else {
+        Ok(())
+    }
+}
+
+/// Enums all the cardinal directions.
+/// #Examples
+/// If I am in cell `(4, 5)` and move `NE`, I go to cell `(3, 6)`.
+#[derive(Debug, Clone, Copy)]
+pub enum Direction {
+    North,
+    NE,
+    East,
+    SE,
+    South,
+    SW,
+    West,
+    NW,
+}
+
+/// Lists all cardinal directions from `Direction`.
+pub const DIRECTIONS: [Direction; 8] = [
+    Direction::North,
+    Direction::NE,
+    Direction::East,
+    Direction::SE,
+    Direction::South,
+    Direction::SW,
+    Direction::West,
+    Direction::NW
+];
+
+/// Coordinates of a cell, given by a row and a column.
+/// Follows matrices conventions (see <https://en.wikipedia.org/wiki/Matrix_(mathematics)>) but for starting indexes at 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Coord {
+    row: usize,
+    col: usize,
+}
+
+impl Coord {
+
+    pub fn new(row: usize, col: usize) -> Coord {
+        Coord {
+            row: row,
+            col: col,
+        }
+    }
+
+    /// Returns coordinate's components.
+    pub fn get_row_col(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    /// Returns coordinate's first component.
+    pub fn get_row(&self) -> usize {
+        self.row
+    }
+
+    /// Returns coordinate's second component.
+    pub fn get_col(&self) -> usize {
+        self.col
+    }
+
+    /// Checks both upper and lower bounds, on a board of the given size.
+    pub fn check_bounds(&self, size: usize) -> Result<()> {
+        if self.row >= size || self.col >= size {
+            Err(ReversiError::OutOfBoundCoord(*self))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Produces new indexes moving along a direction and checking for out-of-bound errors (meaning sub-zero indexes).
+    pub fn step(&mut self, dir: Direction) -> Result<()> {
+        match dir {
+            Direction::North    if self.row > 0                     => Ok({self.row -= 1;}),
+            Direction::NE       if self.row > 0                     => Ok({self.row -= 1; self.col += 1;}),
+            Direction::East                                         => Ok({self.col += 1;}),
+            Direction::SE                                           => Ok({self.row += 1; self.col += 1;}),
+            Direction::South                                        => Ok({self.row += 1;}),
+            Direction::SW       if self.col > 0                     => Ok({self.row += 1; self.col -= 1;}),
+            Direction::West     if self.col > 0                     => Ok({self.col -= 1;}),
+            Direction::NW       if self.row > 0 && self.col > 0     => Ok({self.row -= 1; self.col -= 1;}),
+            _ => Err(ReversiError::OutOfBoundStep(*self, dir)),
+        }
+    }
+}
+
+impl fmt::Display for Coord {
+    /// Writes the name of the cell, e.g. `f5`: its column as a letter and its row as a number.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.col as u8) as char, self.row + 1)
+    }
+}
+
+impl FromStr for Coord {
+    type Err = ReversiError;
+
+    /// Reads the name of a cell, with the column letter either in lower or upper case and either before or after
+    /// the row number, e.g. `f5`, `F5` or `5f`. The cell must fit on the largest board,
+    /// while whether it is on a given board is left to check.
+    fn from_str(name: &str) -> Result<Coord> {
+        let name: String = name.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+        let letters: Vec<char> = name.chars().filter(|c| c.is_alphabetic()).collect();
+        let digits: String = name.chars().filter(|c| c.is_digit(10)).collect();
+        let letter_first = name.starts_with(|c: char| c.is_alphabetic());
+        let letter_last = name.ends_with(|c: char| c.is_alphabetic());
+
+        if letters.len() != 1 || digits.len() + 1 != name.len() || letter_first == letter_last || letters[0] < 'a' {
+            return Err(ReversiError::InvalidCoord);
+        }
+        match digits.parse::<usize>() {
+            Ok(row) if row >= 1 => {
+                let coord = Coord::new(row - 1, letters[0] as usize - 'a' as usize);
+                try!(coord.check_bounds(MAX_BOARD_SIZE).map_err(|_| ReversiError::InvalidCoord));
+                Ok(coord)
+            }
+            _ => Err(ReversiError::InvalidCoord),
+        }
+    }
+}
+
+/// A disk is characterized by its two sides, one Dark and one Light.
+#[derive(Debug, Clone, Copy)]
+pub struct Disk(Side);
+
+impl Disk {
+    /// Creates a new disk with given side.
+    pub fn new(side: Side) -> Disk {
+        Disk(side)
+    }
+
+    /// Return's a disk's side.
+    pub fn get_side(&self) -> Side {
+        self.0
+    }
+
+    /// Turns the disk on the other side.
+    pub fn flip(&mut self) {
+        self.0 = self.0.opposite();
+    }
+}
+
+/// Each cell in the board can either be empty or taken by one of the players.
+pub type Cell = Option<Disk>;
+
+/// `Board` is the type of boards. It stores the disks of each side as a bitboard (see `bitboard`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Board {
+    size: usize,
+    dark: Bits,
+    light: Bits,
+}
+
+impl Board {
+    /// Creates a new empty board, given its number of cells per side.
+    pub fn new(size: usize) -> Result<Board> {
+        try!(check_size(size));
+        Ok(Board {
+            size: size,
+            dark: Bits::empty(),
+            light: Bits::empty(),
+        })
+    }
+
+    /// Returns the number of cells per side.
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the set of all the cells of the board.
+    pub fn get_cells_bits(&self) -> Bits {
+        Bits::board(self.size)
+    }
+
+    /// Iterates over the cells and their coordinates, row by row.
+    pub fn iter(&self) -> Cells {
+        Cells {
+            board: self,
+            index: 0,
+        }
+    }
+
+    /// Returns the cells, row by row.
+    pub fn get_all_cells(&self) -> Vec<Vec<Cell>> {
+        (0..self.size).map(|row| {
+            (0..self.size).map(|col| self.get_cell(Coord::new(row, col)).expect("This cannot fail")).collect()
+        }).collect()
+    }
+
+    /// Returns a non-mutable cell.
+    pub fn get_cell(&self, coord: Coord) -> Result<Cell> {
+        try!(coord.check_bounds(self.size));
+        let bit = bitboard::from_coord(coord);
+        Ok(if self.dark.intersects(bit) {
+            Some(Disk::new(Side::Dark))
+        } else if self.light.intersects(bit) {
+            Some(Disk::new(Side::Light))
+        } else {
+            None
+        })
+    }
+
+    /// Returns a non-mutable disk.
+    pub fn get_disk(&self, coord: Coord) -> Result<Disk> {
+        match try!(self.get_cell(coord)) {
+            None => Err(ReversiError::EmptyCell(coord)),
+            Some(disk) => Ok(disk),
+        }
+    }
+
+    /// Flips the disk on a non-empty cell.
+    pub fn flip_disk(&mut self, coord: Coord) -> Result<()> {
+        try!(self.get_disk(coord));
+        let bit = bitboard::from_coord(coord);
+        self.dark ^= bit;
+        self.light ^= bit;
+        Ok(())
+    }
+
+    /// Place a disk on an empty cell.
+    pub fn place_disk(&mut self, side: Side, coord: Coord) -> Result<()> {
+        match try!(self.get_cell(coord)) {
+            Some(_) => Err(ReversiError::CellAlreadyTaken(coord)),
+            None => Ok(*self.get_mut_bits(side) |= bitboard::from_coord(coord)),
+        }
+    }
+
+    /// Returns the set of cells taken by a side's disks.
+    pub fn get_bits(&self, side: Side) -> Bits {
+        match side {
+            Side::Dark => self.dark,
+            Side::Light => self.light,
+        }
+    }
+
+    /// Returns a mutable reference to the set of cells taken by a side (which is why it's private).
+    fn get_mut_bits(&mut self, side: Side) -> &mut Bits {
+        match side {
+            Side::Dark => &mut self.dark,
+            Side::Light => &mut self.light,
+        }
+    }
+
+    /// Returns the set of cells where a side can legally move.
+    pub fn legal_moves(&self, side: Side) -> Bits {
+        bitboard::legal_moves(self.get_bits(side), self.get_bits(side.opposite()), self.get_cells_bits())
+    }
+
+    /// Places a disk of the given side and flips all the disks it eats.
+    /// It returns the number of flipped disks, or an error if the move is not legal (in which case the board is left untouched).
+    pub fn play(&mut self, side: Side, coord: Coord) -> Result<u16> {
+        if try!(self.get_cell(coord)).is_some() {
+            return Err(ReversiError::CellAlreadyTaken(coord));
+        }
+        let bit = bitboard::from_coord(coord);
+        let flipped = bitboard::flips(self.get_bits(side), self.get_bits(side.opposite()), bit);
+        if flipped.is_empty() {
+            return Err(ReversiError::IllegalMove(coord));
+        }
+        *self.get_mut_bits(side) |= flipped | bit;
+        *self.get_mut_bits(side.opposite()) &= !flipped;
+        Ok(flipped.count_ones() as u16)
+    }
+}
+
+/// Iterator over the cells of a board, row by row, given with their coordinates (see `Board::iter`).
+pub struct Cells<'a> {
+    board: &'a Board,
+    index: usize,
+}
+
+impl<'a> Iterator for Cells<'a> {
+    type Item = (Coord, Cell);
+
+    fn next(&mut self) -> Option<(Coord, Cell)> {
+        let size = self.board.size;
+        if self.index >= size * size {
+            return None;
+        }
+        let coord = Coord::new(self.index / size, self.index % size);
+        self.index += 1;
+        Some((coord, self.board.get_cell(coord).expect("The cell is on the board")))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.board.size * self.board.size - self.index;
+        (left, Some(left))
+    }
+}
+
+impl<'a> IntoIterator for &'a Board {
+    type Item = (Coord, Cell);
+    type IntoIter = Cells<'a>;
+
+    fn into_iter(self) -> Cells<'a> {
+        self.iter()
+    }
+}
+
+impl fmt::Display for Board {
+    /// Writes the board as in GGF: the number of cells per side, then the cells row by row
+    /// (`*` for Dark, `O` for Light, `-` for empty), e.g. `4 -----*O--O*-----`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{} ", self.size));
+        for (_, cell) in self {
+            try!(write!(f, "{}", match cell {
+                Some(disk) if disk.get_side() == Side::Dark => '*',
+                Some(_) => 'O',
+                None => '-',
+            }));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Board {
+    type Err = ReversiError;
+
+    /// Reads a board written as by `Display`, ignoring whitespace, e.g. `4 ---- -*O- -O*- ----`.
+    fn from_str(text: &str) -> Result<Board> {
+        let mut words = text.split_whitespace();
+        let size: usize = try!(words.next().and_then(|word| word.parse().ok()).ok_or(ReversiError::InvalidPosition));
+        let mut board = try!(Board::new(size));
+        let chars: Vec<char> = words.flat_map(|word| word.chars()).collect();
+        if chars.len() != size * size {
+            return Err(ReversiError::InvalidPosition);
+        }
+        for (index, &c) in chars.iter().enumerate() {
+            let coord = Coord::new(index / size, index % size);
+            match c {
+                '*' => try!(board.place_disk(Side::Dark, coord)),
+                'O' | 'o' => try!(board.place_disk(Side::Light, coord)),
+                '-' => {}
+                _ => return Err(ReversiError::InvalidPosition),
+            }
+        }
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_moves_one_cell_in_every_direction() {
+        let expected = [(3, 4), (3, 5), (4, 5), (5, 5), (5, 4), (5, 3), (4, 3), (3, 3)];
+        for (&dir, &row_col) in DIRECTIONS.iter().zip(expected.iter()) {
+            let mut coord = Coord::new(4, 4);
+            coord.step(dir).unwrap();
+            assert_eq!(coord.get_row_col(), row_col, "stepping {:?}", dir);
+        }
+    }
+
+    #[test]
+    fn step_stops_at_lower_bounds() {
+        // From the top-left corner, only East, SE and South stay on the board
+        for &dir in &DIRECTIONS {
+            let mut coord = Coord::new(0, 0);
+            let stays = match dir {
+                Direction::East | Direction::SE | Direction::South => true,
+                _ => false,
+            };
+            assert_eq!(coord.step(dir).is_ok(), stays, "stepping {:?}", dir);
+            if !stays {
+                // A failed step leaves the coordinates as they were
+                assert_eq!(coord.get_row_col(), (0, 0));
+            }
+        }
+        assert!(Coord::new(0, 5).step(Direction::NE).is_err());
+        assert!(Coord::new(5, 0).step(Direction::SW).is_err());
+    }
+
+    #[test]
+    fn steps_beyond_upper_bounds_are_caught_by_check_bounds() {
+        let last = DEFAULT_BOARD_SIZE - 1;
+        for &dir in &[Direction::East, Direction::SE, Direction::South] {
+            let mut coord = Coord::new(last, last);
+            coord.step(dir).unwrap();
+            assert!(coord.check_bounds(DEFAULT_BOARD_SIZE).is_err(), "stepping {:?}", dir);
+        }
+        let board = Board::new(DEFAULT_BOARD_SIZE).unwrap();
+        assert!(board.get_cell(Coord::new(last, DEFAULT_BOARD_SIZE)).is_err());
+        assert!(board.get_cell(Coord::new(last, last)).is_ok());
+    }
+
+    #[test]
+    fn board_sizes_are_checked() {
+        assert!(Board::new(MIN_BOARD_SIZE).is_ok());
+        assert!(Board::new(MAX_BOARD_SIZE).is_ok());
+        assert!(Board::new(MIN_BOARD_SIZE - 2).is_err());
+        assert!(Board::new(MAX_BOARD_SIZE + 2).is_err());
+        assert!(Board::new(DEFAULT_BOARD_SIZE + 1).is_err());
+    }
+
+    #[test]
+    fn coords_are_read_back_from_their_names() {
+        for row in 0..MAX_BOARD_SIZE {
+            for col in 0..MAX_BOARD_SIZE {
+                let coord = Coord::new(row, col);
+                assert_eq!(coord.to_string().parse::<Coord>().unwrap(), coord);
+            }
+        }
+        assert_eq!(Coord::new(4, 5).to_string(), "f5");
+        assert_eq!("F5".parse::<Coord>().unwrap(), Coord::new(4, 5));
+        assert_eq!("5f".parse::<Coord>().unwrap(), Coord::new(4, 5));
+        for name in &["", "f", "5", "f0", "ff5", "f5f", "q1", "a17"] {
+            assert!(name.parse::<Coord>().is_err(), "{} was read", name);
+        }
+    }
+
+    #[test]
+    fn boards_are_read_back_from_their_text() {
+        let mut board = Board::new(4).unwrap();
+        board.place_disk(Side::Dark, Coord::new(1, 1)).unwrap();
+        board.place_disk(Side::Light, Coord::new(1, 2)).unwrap();
+        board.place_disk(Side::Light, Coord::new(3, 0)).unwrap();
+        assert_eq!(board.to_string(), "4 -----*O-----O---");
+        assert_eq!(board.to_string().parse::<Board>().unwrap(), board);
+        assert_eq!("4 ---- -*O- ---- O---".parse::<Board>().unwrap(), board);
+        assert!("4 ---- -*O- ---- O--".parse::<Board>().is_err());
+        assert!("4 ---- -*X- ---- O---".parse::<Board>().is_err());
+        assert!("5 -------------------------".parse::<Board>().is_err());
+    }
+
+    #[test]
+    fn boards_iterate_over_their_cells_row_by_row() {
+        let mut board = Board::new(4).unwrap();
+        board.place_disk(Side::Dark, Coord::new(2, 3)).unwrap();
+        let cells: Vec<(Coord, Cell)> = board.iter().collect();
+        assert_eq!(cells.len(), 16);
+        for (index, &(coord, cell)) in cells.iter().enumerate() {
+            assert_eq!(coord, Coord::new(index / 4, index % 4));
+            assert_eq!(cell.map(|disk| disk.get_side()), board.get_cell(coord).unwrap().map(|disk| disk.get_side()));
+        }
+        assert_eq!((&board).into_iter().filter(|&(_, cell)| cell.is_some()).count(), 1);
+    }
+}
@@ -0,0 +1,324 @@
+//! Implementation of a complete Reversi match.
+
+use std::marker::{PhantomData, Sized};
+use {ReversiError, Result, Side};
+use board::*;
+use turn::*;
+
+
+pub enum PlayerAction<A> {
+    Move(Coord),
+    Undo,
+    Redo,
+    Other(A),
+}
+
+/// Being able to make moves is the trait characterizing players.
+pub trait IsPlayer<A> {
+    fn make_move(&self, turn: &Turn) -> Result<PlayerAction<A>>;
+}
+
+/// A game is given by a list of past turns (with the successive move), a current turn, and the two players.
+/// When stepping back through the history, the moves stepped over are kept so that one can step forward again.
+pub struct Game<'a, A, D: 'a + ?Sized + IsPlayer<A>, L: 'a + ?Sized + IsPlayer<A>> {
+    current_turn: Turn,
+    turns_history: Vec<(Turn, Coord)>,
+    moves_ahead: Vec<Coord>,
+    dark:  &'a D,
+    light: &'a L,
+    phantom: PhantomData<A>
+}
+
+impl<'a, A, D: 'a + ?Sized + IsPlayer<A>, L: 'a + ?Sized + IsPlayer<A>> Game<'a, A, D, L> {
+
+    /// Creates a new game, with first turn already set and empty turns' history.
+    /// It requires the two players as input.
+    pub fn new(dark: &'a D, light: &'a L) -> Game<'a, A, D, L> where D: IsPlayer<A>, L: IsPlayer<A> {
+        Game::starting_from(dark, light, Turn::first_turn())
+    }
+
+    /// Creates a new game starting from a given turn, e.g. on a board of another size or from a custom position.
+    pub fn starting_from(dark: &'a D, light: &'a L, first_turn: Turn) -> Game<'a, A, D, L> where D: IsPlayer<A>, L: IsPlayer<A> {
+        Game {
+            current_turn: first_turn,
+            turns_history: vec![],
+            moves_ahead: vec![],
+            dark: dark,
+            light: light,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Gets the board of the current turn.
+    pub fn get_current_board(&self) -> &Board {
+        self.current_turn.get_board()
+    }
+
+    /// Gets current turn.
+    pub fn get_current_turn(&self) -> &Turn {
+        &self.current_turn
+    }
+
+    /// Gets current score.
+    pub fn get_current_score(&self) -> (u16, u16) {
+        self.current_turn.get_score()
+    }
+
+    /// Gets the state of the current turn.
+    pub fn get_current_state(&self) -> State {
+        self.current_turn.get_state()
+    }
+
+    /// Gets the turn the game started from.
+    pub fn get_first_turn(&self) -> &Turn {
+        self.turns_history.first().map_or(&self.current_turn, |&(ref turn, _)| turn)
+    }
+
+    /// Gets the moves played so far, from the first turn.
+    pub fn get_moves(&self) -> Vec<Coord> {
+        self.turns_history.iter().map(|&(_, coord)| coord).collect()
+    }
+
+    /// Gets the past turns, each with the move played in it.
+    pub fn get_history(&self) -> &[(Turn, Coord)] {
+        &self.turns_history
+    }
+
+    /// Returns true if the player to move (or, if the game is ended, the one who did not make the last move)
+    /// has a move of its own to undo.
+    pub fn can_undo(&self) -> bool {
+        match self.get_current_state() {
+            Some(side) => self.turns_history.iter().any(|&(ref turn, _)| turn.get_state() == Some(side)),
+            None => match self.turns_history.split_last() {
+                Some((&(ref last_turn, _), earlier)) => {
+                    let side = last_turn.get_state().map(|last_side| last_side.opposite());
+                    earlier.iter().any(|&(ref turn, _)| turn.get_state() == side)
+                }
+                None => false,
+            },
+        }
+    }
+
+    /// Returns true if there are undone moves which can be redone.
+    pub fn can_redo(&self) -> bool {
+        !self.moves_ahead.is_empty()
+    }
+
+    /// Goes back by a single move, whoever played it, and returns that move.
+    pub fn step_back(&mut self) -> Result<Coord> {
+        let (previous_turn, coord) = try!(self.turns_history.pop().ok_or(ReversiError::NoUndo));
+        self.current_turn = previous_turn;
+        self.moves_ahead.push(coord);
+        Ok(coord)
+    }
+
+    /// Plays again the last move stepped back over, and returns it.
+    pub fn step_forward(&mut self) -> Result<Coord> {
+        let coord = try!(self.moves_ahead.pop().ok_or(ReversiError::NoRedo));
+        let new_turn = try!(self.current_turn.make_move(coord));
+        self.turns_history.push((self.current_turn.clone(), coord));
+        self.current_turn = new_turn;
+        Ok(coord)
+    }
+
+    /// Plays a list of moves, e.g. to resume a saved game.
+    /// If any of them is illegal, the game is left as it was.
+    pub fn play_moves(&mut self, moves: &[Coord]) -> Result<()> {
+        let (turn, history, ahead) = (self.current_turn.clone(), self.turns_history.clone(), self.moves_ahead.clone());
+        for &coord in moves {
+            if let Err(err) = self.make_move(coord) {
+                self.current_turn = turn;
+                self.turns_history = history;
+                self.moves_ahead = ahead;
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if the game is ended.
+    pub fn is_ended(&self) -> bool {
+        self.get_current_state().is_none()
+    }
+
+    /// It has the correct player return an action and applies its effects.
+    pub fn play_turn(&mut self) -> Result<PlayerAction<A>> {
+        let action = match self.current_turn.get_state() {
+            None => return Err(ReversiError::EndedGame),
+            Some(Side::Dark)  => try!(self.dark.make_move(&self.current_turn)),
+            Some(Side::Light) => try!(self.light.make_move(&self.current_turn)),
+        };
+
+        match action {
+            // If that move is legal, it is applied and the turns' history is updated.
+            PlayerAction::Move(coord) => try!(self.make_move(coord)),
+            PlayerAction::Undo => try!(self.undo()),
+            PlayerAction::Redo => try!(self.redo()),
+            _ => {}
+        }
+
+        Ok(action)
+    }
+
+    /// A move (given by `coord`) is applied. If that move is legal, game's history is updated.
+    /// The moves stepped back over are forgotten, as the game now takes another course.
+    fn make_move(&mut self, coord: Coord) -> Result<()> {
+        let new_turn = try!(self.current_turn.make_move(coord));
+        self.turns_history.push((self.current_turn.clone(), coord));
+        self.current_turn = new_turn;
+        self.moves_ahead.clear();
+        Ok(())
+    }
+
+    /// Undo last move(s) till the player asking for undoing can play again.
+    /// Undone moves can be redone, until a new move is made.
+    fn undo(&mut self) -> Result<()> {
+        let backup = (self.current_turn.clone(), self.turns_history.clone(), self.moves_ahead.clone());
+        // If the game is ended, the player asking is the one who did not make the last move
+        let side = match self.get_current_state() {
+            None => {
+                try!(self.step_back());
+                self.get_current_state().expect("Moves are played in running turns").opposite()
+            }
+            Some(current_side) => current_side,
+        };
+        loop {
+            if self.step_back().is_err() {
+                self.current_turn = backup.0;
+                self.turns_history = backup.1;
+                self.moves_ahead = backup.2;
+                return Err(ReversiError::NoUndo);
+            }
+            if self.get_current_state() == Some(side) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Redo undone move(s) till the player asking for redoing can play again, or there are no more to redo.
+    fn redo(&mut self) -> Result<()> {
+        let side = self.get_current_state();
+        try!(self.step_forward());
+        while !self.moves_ahead.is_empty() && self.get_current_state() != side {
+            try!(self.step_forward());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use {ReversiError, Side};
+    use board::Coord;
+    use turn::Turn;
+    use Result;
+    use super::*;
+
+    /// A player following a script of actions, which may play both sides.
+    struct Script(RefCell<Vec<PlayerAction<()>>>);
+
+    impl Script {
+        fn new(mut actions: Vec<PlayerAction<()>>) -> Script {
+            actions.reverse();
+            Script(RefCell::new(actions))
+        }
+    }
+
+    impl IsPlayer<()> for Script {
+        fn make_move(&self, _: &Turn) -> Result<PlayerAction<()>> {
+            Ok(self.0.borrow_mut().pop().expect("The script is over"))
+        }
+    }
+
+    fn play(coord: (usize, usize)) -> PlayerAction<()> {
+        PlayerAction::Move(Coord::new(coord.0, coord.1))
+    }
+
+    #[test]
+    fn undo_goes_back_to_the_undoing_sides_turn() {
+        // f5 d6, then Dark undoes and redoes, and plays c3
+        let script = Script::new(vec![play((4, 5)), play((5, 3)), PlayerAction::Undo, PlayerAction::Redo, play((2, 2))]);
+        let mut game = Game::new(&script, &script);
+        game.play_turn().unwrap();
+        game.play_turn().unwrap();
+        let after_d6 = game.get_current_turn().to_position();
+        assert!(game.can_undo());
+
+        game.play_turn().unwrap();
+        assert_eq!(game.get_current_turn().to_position(), Turn::first_turn().to_position());
+        assert!(game.get_moves().is_empty());
+        assert!(game.can_redo());
+        assert!(!game.can_undo());
+
+        game.play_turn().unwrap();
+        assert_eq!(game.get_current_turn().to_position(), after_d6);
+        assert_eq!(game.get_current_state(), Some(Side::Dark));
+
+        game.play_turn().unwrap();
+        assert!(!game.can_redo());
+        assert_eq!(game.get_moves(), vec![Coord::new(4, 5), Coord::new(5, 3), Coord::new(2, 2)]);
+    }
+
+    #[test]
+    fn new_moves_forget_the_moves_undone() {
+        let script = Script::new(vec![play((4, 5)), play((5, 3)), PlayerAction::Undo, play((5, 4))]);
+        let mut game = Game::new(&script, &script);
+        for _ in 0..4 {
+            game.play_turn().unwrap();
+        }
+        assert!(!game.can_redo());
+        assert_eq!(game.get_moves(), vec![Coord::new(5, 4)]);
+    }
+
+    #[test]
+    fn undo_without_own_moves_changes_nothing() {
+        let script = Script::new(vec![play((4, 5)), PlayerAction::Undo, PlayerAction::Redo]);
+        let mut game = Game::new(&script, &script);
+        game.play_turn().unwrap();
+        assert!(!game.can_undo());
+        match game.play_turn() {
+            Err(ReversiError::NoUndo) => {}
+            _ => panic!("Light has no move to undo"),
+        }
+        match game.play_turn() {
+            Err(ReversiError::NoRedo) => {}
+            _ => panic!("There is no move to redo"),
+        }
+        assert_eq!(game.get_moves(), vec![Coord::new(4, 5)]);
+        assert_eq!(game.get_current_state(), Some(Side::Light));
+    }
+
+    #[test]
+    fn undo_after_a_pass_takes_back_a_single_move() {
+        // After c1 Light has to pass, so Dark undoing only takes back c1
+        let start = Turn::from_position("4 *O-- ---- ---- --O* *").unwrap();
+        let script = Script::new(vec![play((0, 2)), PlayerAction::Undo, play((0, 2)), play((3, 1))]);
+        let mut game = Game::starting_from(&script, &script, start.clone());
+        game.play_turn().unwrap();
+        assert_eq!(game.get_current_state(), Some(Side::Dark));
+        game.play_turn().unwrap();
+        assert_eq!(game.get_current_turn().to_position(), start.to_position());
+
+        // Once Dark wipes Light out, Light has nothing to undo and the game is over
+        game.play_turn().unwrap();
+        game.play_turn().unwrap();
+        assert!(game.is_ended());
+        assert!(!game.can_undo());
+        match game.play_turn() {
+            Err(ReversiError::EndedGame) => {}
+            _ => panic!("The game is ended"),
+        }
+    }
+
+    #[test]
+    fn illegal_moves_leave_the_game_as_it_was() {
+        let script = Script::new(vec![play((0, 0))]);
+        let mut game = Game::new(&script, &script);
+        assert!(game.play_turn().is_err());
+        assert!(game.get_moves().is_empty());
+        assert!(game.play_moves(&[Coord::new(4, 5), Coord::new(4, 5)]).is_err());
+        assert!(game.get_moves().is_empty());
+        assert_eq!(game.get_first_turn().to_position(), Turn::first_turn().to_position());
+    }
+}
@@ -1,17 +1,24 @@
 //! The `reversi` library (ver. 0.1.0) provides the main structures and mechanics required to run a Reversi game.
 //! In view of possible AIs developement, the library keeps an eye on performances.
+//! It is used by RUSThello, but does not depend on it: other tools can link to it as the `reversi` crate.
 //! Released under MIT license.
 
+#![cfg_attr(test, feature(rand))]
+
 // Roadmap to 0.1.0:
 // ReversiError::NoUndo needs a turn::Turn value
-// board::Board needs IntoIter so that its (unique) component can be made private
 
+#[cfg(test)]
+extern crate rand;
+
+pub mod bitboard;
 pub mod board;
 pub mod turn;
 pub mod game;
+pub mod record;
 
 use std::{error, fmt, result};
-use self::board::{Coord, Direction};
+use board::{Coord, Direction};
 
 /// The errors that may be generated by running a Reversi game.
 #[derive(Debug, Clone, Copy)]
@@ -28,8 +35,18 @@ pub enum ReversiError {
     IllegalMove(Coord),
     /// Undoing a turn is not possible
     NoUndo,
+    /// There is no undone turn to redo
+    NoRedo,
     /// It has been tried to move when the game was already ended.
     EndedGame,
+    /// A game record could not be read.
+    InvalidRecord,
+    /// Boards must have an even number of cells per side, within the supported range.
+    InvalidBoardSize(usize),
+    /// A starting position could not be read.
+    InvalidPosition,
+    /// The name of a cell could not be read.
+    InvalidCoord,
 }
 
 /// Aliasing given by taking `ReversiError` as standard error value.
@@ -38,13 +55,19 @@ pub type Result<T> = result::Result<T, ReversiError>;
 impl fmt::Display for ReversiError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            ReversiError::OutOfBoundCoord(coord) => write!(f, "Out of bound coordinates: {:?}", coord),
-            ReversiError::OutOfBoundStep(coord, dir) => write!(f, "Out of bound step: {:?} going {:?}", coord, dir),
-            ReversiError::CellAlreadyTaken(coord) => write!(f, "The cell you want to move to is already taken: {:?}", coord),
-            ReversiError::IllegalMove(coord) => write!(f, "Illegal move: {:?}", coord),
-            ReversiError::EmptyCell(coord) => write!(f, "The cell you want is empty: {:?}", coord),
+            ReversiError::OutOfBoundCoord(coord) => write!(f, "Out of bound coordinates: {}", coord),
+            ReversiError::OutOfBoundStep(coord, dir) => write!(f, "Out of bound step: {} going {:?}", coord, dir),
+            ReversiError::CellAlreadyTaken(coord) => write!(f, "The cell you want to move to is already taken: {}", coord),
+            ReversiError::IllegalMove(coord) => write!(f, "Illegal move: {}", coord),
+            ReversiError::EmptyCell(coord) => write!(f, "The cell you want is empty: {}", coord),
             ReversiError::NoUndo => write!(f, "Undoing is not possible"),
+            ReversiError::NoRedo => write!(f, "Redoing is not possible"),
             ReversiError::EndedGame => write!(f, "The game is already ended"),
+            ReversiError::InvalidRecord => write!(f, "The game record is not valid"),
+            ReversiError::InvalidBoardSize(size) => write!(f, "Invalid board size: {} (it must be even, from {} to {})",
+                size, board::MIN_BOARD_SIZE, board::MAX_BOARD_SIZE),
+            ReversiError::InvalidPosition => write!(f, "The position is not valid"),
+            ReversiError::InvalidCoord => write!(f, "The cell name is not valid"),
         }
     }
 }
@@ -58,7 +81,12 @@ impl error::Error for ReversiError {
             ReversiError::IllegalMove(_) => "Illegal move",
             ReversiError::EmptyCell(_) => "The cell you want is empty",
             ReversiError::NoUndo => "Undoing is not possible",
+            ReversiError::NoRedo => "Redoing is not possible",
             ReversiError::EndedGame => "The game is already ended",
+            ReversiError::InvalidRecord => "The game record is not valid",
+            ReversiError::InvalidBoardSize(_) => "Invalid board size",
+            ReversiError::InvalidPosition => "The position is not valid",
+            ReversiError::InvalidCoord => "The cell name is not valid",
         }
     }
 
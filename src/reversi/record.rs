@@ -0,0 +1,241 @@
+//! Game records, to save games and load them back.
+//! Two formats are supported: plain transcripts, listing the moves one after the other (e.g. `f5d6c3d3c4`),
+//! and the Generic Game Format (GGF) used by Othello servers, which also names the players and the result
+//! and allows games on any board, from any starting position.
+
+use {ReversiError, Result, Side};
+use board::Coord;
+use turn::Turn;
+
+/// The record of a game: its players, its starting position and the moves played from it.
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub dark: String,
+    pub light: String,
+    pub start: Turn,
+    pub moves: Vec<Coord>,
+}
+
+impl Record {
+    pub fn new(dark: &str, light: &str, start: Turn, moves: Vec<Coord>) -> Record {
+        Record {
+            dark: dark.to_string(),
+            light: light.to_string(),
+            start: start,
+            moves: moves,
+        }
+    }
+
+    /// Returns whether the game starts from the standard position, which plain transcripts assume.
+    pub fn has_standard_start(&self) -> bool {
+        self.start.to_position() == Turn::first_turn().to_position()
+    }
+
+    /// Parses a record either in GGF or as a plain transcript, and checks that its moves are legal.
+    pub fn parse(text: &str) -> Result<Record> {
+        let record = if text.trim_left().starts_with("(;") {
+            try!(Record::parse_ggf(text))
+        } else {
+            try!(Record::parse_transcript(text))
+        };
+        try!(record.turns());
+        Ok(record)
+    }
+
+    /// All turns of the game, from the first one to the current one.
+    pub fn turns(&self) -> Result<Vec<Turn>> {
+        let mut turns = vec![self.start.clone()];
+        for &coord in &self.moves {
+            let next = try!(turns.last().expect("There is a first turn").make_move(coord));
+            turns.push(next);
+        }
+        Ok(turns)
+    }
+
+    /// Writes the moves as a plain transcript.
+    pub fn to_transcript(&self) -> String {
+        self.moves.iter().map(|&coord| coord.to_string()).collect()
+    }
+
+    /// Writes the game in GGF. Passes are written explicitly, and the result is given once the game is ended.
+    pub fn to_ggf(&self) -> Result<String> {
+        let turns = try!(self.turns());
+        let mut ggf = format!("(;GM[Othello]PC[RUSThello]PB[{}]PW[{}]TY[{}]", escape(&self.dark), escape(&self.light), self.start.get_size());
+
+        let last = turns.last().expect("There is a first turn");
+        if last.is_endgame() {
+            match -last.get_score_diff() {
+                0 => ggf.push_str("RE[0]"),
+                diff => ggf.push_str(&format!("RE[{:+}]", diff)),
+            }
+        }
+        ggf.push_str(&format!("BO[{}]", self.start.to_position()));
+
+        // Who would move if nobody passed
+        let mut expected = self.start.get_state().unwrap_or(Side::Dark);
+        for (turn, &coord) in turns.iter().zip(self.moves.iter()) {
+            let side = turn.get_state().expect("Moves are played in running turns");
+            if side != expected {
+                ggf.push_str(&format!("{}[PA]", side_property(expected)));
+            }
+            ggf.push_str(&format!("{}[{}]", side_property(side), coord));
+            expected = side.opposite();
+        }
+
+        ggf.push_str(";)");
+        Ok(ggf)
+    }
+
+    fn parse_transcript(text: &str) -> Result<Record> {
+        Ok(Record::new("", "", Turn::first_turn(), try!(parse_moves(text))))
+    }
+
+    fn parse_ggf(text: &str) -> Result<Record> {
+        let mut record = Record::new("", "", Turn::first_turn(), Vec::new());
+        let mut chars = text.chars();
+        let mut name = String::new();
+
+        while let Some(c) = chars.next() {
+            if c.is_ascii_uppercase() {
+                name.push(c);
+            } else if c == '[' {
+                // Read the value of the property, which may contain escaped brackets
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some('\\') => value.extend(chars.next()),
+                        Some(c) => value.push(c),
+                        None => return Err(ReversiError::InvalidRecord),
+                    }
+                }
+
+                match &*name {
+                    "PB" => record.dark = value,
+                    "PW" => record.light = value,
+                    "BO" => record.start = try!(Turn::from_position(&value)),
+                    "B" | "W" => {
+                        // Moves may be followed by an evaluation and a time, e.g. `f5/1.00/12`
+                        let cell = value.split('/').next().unwrap_or("");
+                        if !cell.trim().eq_ignore_ascii_case("pa") {
+                            record.moves.push(try!(cell.parse().map_err(|_| ReversiError::InvalidRecord)));
+                        }
+                    }
+                    _ => {}
+                }
+                name.clear();
+            } else {
+                name.clear();
+            }
+        }
+
+        Ok(record)
+    }
+}
+
+/// Parses the cells of a transcript, e.g. `f5d6c3`, without checking that they are legal moves from any position.
+pub fn parse_moves(text: &str) -> Result<Vec<Coord>> {
+    // Every move starts with its column letter
+    let mut names: Vec<String> = Vec::new();
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        if c.is_alphabetic() {
+            names.push(String::new());
+        }
+        try!(names.last_mut().ok_or(ReversiError::InvalidRecord)).push(c);
+    }
+    let mut moves = Vec::with_capacity(names.len());
+    for name in names {
+        moves.push(try!(name.parse().map_err(|_| ReversiError::InvalidRecord)));
+    }
+    Ok(moves)
+}
+
+/// The GGF property of the moves of a side.
+fn side_property(side: Side) -> &'static str {
+    match side {
+        Side::Dark => "B",
+        Side::Light => "W",
+    }
+}
+
+/// Escapes the characters which would end a GGF property.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace(']', "\\]")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use board::Coord;
+    use turn::Turn;
+    use super::*;
+
+    /// Dark takes c1, Light has to pass, and Dark ends the game with a2, winning by 6 disks.
+    const PASSING: &'static str = "4 *O-- ---- O--- *--- *";
+
+    fn passing_record() -> Record {
+        Record::new("Alice", "Bob", Turn::from_position(PASSING).unwrap(), vec![Coord::new(0, 2), Coord::new(1, 0)])
+    }
+
+    #[test]
+    fn ggf_round_trip() {
+        let moves = vec![Coord::new(4, 5), Coord::new(5, 3), Coord::new(2, 2)];
+        let record = Record::new("Alice", "Bob", Turn::first_turn(), moves.clone());
+        let read = Record::parse(&record.to_ggf().unwrap()).unwrap();
+        assert_eq!((&*read.dark, &*read.light), ("Alice", "Bob"));
+        assert_eq!(read.start.to_position(), Turn::first_turn().to_position());
+        assert_eq!(read.moves, moves);
+        assert!(read.has_standard_start());
+    }
+
+    #[test]
+    fn passes_are_written_and_read_back() {
+        let record = passing_record();
+        let ggf = record.to_ggf().unwrap();
+        assert!(ggf.contains("B[c1]W[PA]B[a2]"), "{}", ggf);
+        let read = Record::parse(&ggf).unwrap();
+        assert_eq!(read.moves, record.moves);
+        assert!(!read.has_standard_start());
+        assert!(read.turns().unwrap().last().unwrap().is_endgame());
+    }
+
+    #[test]
+    fn names_are_escaped() {
+        let mut record = passing_record();
+        record.dark = "A]l\\ice".to_string();
+        let ggf = record.to_ggf().unwrap();
+        assert!(ggf.contains("PB[A\\]l\\\\ice]"), "{}", ggf);
+        assert_eq!(Record::parse(&ggf).unwrap().dark, "A]l\\ice");
+    }
+
+    #[test]
+    fn results_are_from_darks_point_of_view() {
+        assert!(passing_record().to_ggf().unwrap().contains("RE[+6]"));
+        // The same game with the colors swapped
+        let swapped = Turn::from_position("4 O*-- ---- *--- O--- O").unwrap();
+        let record = Record::new("", "", swapped, vec![Coord::new(0, 2), Coord::new(1, 0)]);
+        assert!(record.to_ggf().unwrap().contains("RE[-6]"));
+        // No result before the end
+        assert!(!Record::new("", "", Turn::first_turn(), Vec::new()).to_ggf().unwrap().contains("RE["));
+    }
+
+    #[test]
+    fn moves_may_have_an_evaluation_and_a_time() {
+        let ggf = format!("(;GM[Othello]BO[{}]B[f5/1.00/12]W[D6//3.5]B[c3/-2];)", Turn::first_turn().to_position());
+        let record = Record::parse(&ggf).unwrap();
+        assert_eq!(record.moves, vec![Coord::new(4, 5), Coord::new(5, 3), Coord::new(2, 2)]);
+    }
+
+    #[test]
+    fn transcripts_may_have_whitespace() {
+        let record = Record::parse("  f5 d6\n\tC3\r\n").unwrap();
+        assert_eq!(record.moves, vec![Coord::new(4, 5), Coord::new(5, 3), Coord::new(2, 2)]);
+        assert_eq!(record.to_transcript(), "f5d6c3");
+    }
+
+    #[test]
+    fn truncated_properties_are_invalid() {
+        assert!(Record::parse("(;GM[Othello]PB[Ali").is_err());
+        assert!(Record::parse(&format!("(;GM[Othello]BO[{}]B[f5", Turn::first_turn().to_position())).is_err());
+    }
+}
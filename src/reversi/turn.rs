@@ -0,0 +1,469 @@
+//! Implementation of Reversi rules to play a turn.
+
+use {ReversiError, Result, Side};
+use bitboard;
+use board::*;
+
+/// A turn can be in two states: either running (with a side to play next) or ended.
+pub type State = Option<Side>;
+
+/// A turn is given by a board and by which player has to move next.
+/// For convenience we also annotate current scores.
+#[derive(Debug, Clone)]
+pub struct Turn {
+    board: Board,
+    state: State,
+    score_dark: u16,
+    score_light: u16,
+}
+
+impl Turn {
+    /// Initializing a new first turn on the standard board: starting positions on the board and Dark is the first to play
+    pub fn first_turn() -> Turn {
+        Turn::new_game(DEFAULT_BOARD_SIZE).expect("The default size is valid")
+    }
+
+    /// Initializing a new first turn on a board of any valid size, with the four starting disks at its centre.
+    pub fn new_game(size: usize) -> Result<Turn> {
+        let mut board = try!(Board::new(size));
+        let mid = size / 2;
+        board.place_disk(Side::Dark, Coord::new(mid - 1, mid)).expect("This cannot fail");
+        board.place_disk(Side::Dark, Coord::new(mid, mid - 1)).expect("This cannot fail");
+        board.place_disk(Side::Light, Coord::new(mid - 1, mid - 1)).expect("This cannot fail");
+        board.place_disk(Side::Light, Coord::new(mid, mid)).expect("This cannot fail");
+
+        Ok(Turn {
+            board: board,
+            state: Some(Side::Dark),
+            score_dark: 2,
+            score_light: 2,
+        })
+    }
+
+    /// Reads a position in the format of GGF boards: the number of cells per side, then the cells row by row
+    /// (`*` for Dark, `O` for Light, `-` for empty) and finally the side to move (`*` or `O`, Dark if missing).
+    /// Whitespace is ignored, e.g. `4 -*O- -O*- ---- ---- *`.
+    /// If the side to move cannot play, the other one moves instead, and if neither can the turn is ended.
+    pub fn from_position(position: &str) -> Result<Turn> {
+        let mut words = position.split_whitespace();
+        let size: usize = try!(words.next().and_then(|word| word.parse().ok()).ok_or(ReversiError::InvalidPosition));
+        try!(check_size(size));
+        let mut cells: String = words.collect();
+        // The side to move is the character following the cells, if any
+        let side = if cells.chars().count() == size * size + 1 {
+            match cells.pop() {
+                Some('*') => Side::Dark,
+                Some('O') | Some('o') => Side::Light,
+                _ => return Err(ReversiError::InvalidPosition),
+            }
+        } else {
+            Side::Dark
+        };
+        let board: Board = try!(format!("{} {}", size, cells).parse());
+
+        let mut turn = Turn {
+            board: board,
+            state: None,
+            score_dark: board.get_bits(Side::Dark).count_ones() as u16,
+            score_light: board.get_bits(Side::Light).count_ones() as u16,
+        };
+        turn.state = turn.next_state(side);
+        Ok(turn)
+    }
+
+    /// Writes the position in the format read by `from_position`.
+    pub fn to_position(&self) -> String {
+        format!("{} {}", self.board, match self.state {
+            Some(Side::Light) => 'O',
+            _ => '*',
+        })
+    }
+
+    /// Returns the turn's board
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Returns the board's cell corresponding to the given coordinates.
+    pub fn get_cell(&self, coord: Coord) -> Result<Cell> {
+        self.board.get_cell(coord)
+    }
+
+    /// Returns the turn's status
+    pub fn get_state(&self) -> State {
+        self.state
+    }
+
+    /// Returns whether the turn is an endgame
+    pub fn is_endgame(&self) -> bool {
+        self.state == None
+    }
+
+    /// Returns the number of cells per side of the board.
+    pub fn get_size(&self) -> usize {
+        self.board.get_size()
+    }
+
+    /// Returns the current score of the match.
+    pub fn get_score(&self) -> (u16, u16) {
+        (self.score_dark, self.score_light)
+    }
+
+    /// Returns the difference in score between Light and Dark.
+    pub fn get_score_diff(&self) -> i16 {
+        self.score_light as i16 - self.score_dark as i16
+    }
+
+    /// Returns turn's tempo (how many disks there are on the board).
+    pub fn get_tempo(&self) -> u16 {
+        self.score_light + self.score_dark
+    }
+
+    /// Returns how many cells are still empty.
+    pub fn get_empty_cells(&self) -> u16 {
+        (self.get_size() * self.get_size()) as u16 - self.get_tempo()
+    }
+
+    /// Check whether a given move is legal
+    pub fn check_move (&self, coord: Coord) -> Result<()> {
+
+        if let Some(side) = self.state {
+            if try!(self.board.get_cell(coord)).is_some() { // This also checks `coord`
+                // If a cell is already taken, it's not possible to move there
+                Err(ReversiError::CellAlreadyTaken(coord))
+            } else if self.board.legal_moves(side).intersects(bitboard::from_coord(coord)) {
+                Ok(())
+            } else {
+                // Otherwise, the move is not legal
+                Err(ReversiError::IllegalMove(coord))
+            }
+        } else {
+            // If the game is ended, no further moves are possible
+            Err(ReversiError::EndedGame)
+        }
+    }
+
+    /// Returns the coordinates of all the legal moves of the current player (none if the game is ended).
+    pub fn get_legal_moves(&self) -> Vec<Coord> {
+        match self.state {
+            Some(side) => bitboard::coords(self.board.legal_moves(side)),
+            None => Vec::new(),
+        }
+    }
+
+    /// Current player performs a move, after verifying that it is legal.
+    /// It returns either the new turn or the error preventing the move to be performed.
+    pub fn make_move (&self, coord: Coord) -> Result<Turn> {
+
+        let turn_side = try!(self.state.ok_or(ReversiError::EndedGame));
+        let mut turn_after_move = self.clone();
+        let eating = try!(turn_after_move.board.play(turn_side, coord));
+
+        match turn_side {
+            Side::Dark  => {
+                turn_after_move.score_dark  += eating + 1;
+                turn_after_move.score_light -= eating;
+            }
+            Side::Light => {
+                turn_after_move.score_light += eating + 1;
+                turn_after_move.score_dark  -= eating;
+            }
+        }
+
+        // If a move is legal, the next player to play has to be determined
+        turn_after_move.state = turn_after_move.next_state(turn_side.opposite());
+
+        Ok(turn_after_move)
+    }
+
+    /// Determines who is to play, given the side whose turn it would be.
+    fn next_state(&self, side: Side) -> State {
+        // If that player can make any move at all, it gets the turn
+        // If not, if the other player can make any move at all, it gets the turn
+        // If not (that is, if no player can make any move at all) the game is ended
+        if self.get_empty_cells() == 0 {
+            // Quick check to rule out games with filled up boards as ended.
+            None
+        } else if !self.board.legal_moves(side).is_empty() {
+            Some(side)
+        } else if !self.board.legal_moves(side.opposite()).is_empty() {
+            // If the player cannot move, turn passes to the other player.
+            Some(side.opposite())
+        } else {
+            // If neither platers can move, game is ended.
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{ChaChaRng, Rng, SeedableRng};
+    use {ReversiError, Side};
+    use board::*;
+    use super::Turn;
+
+    /// Reference implementation: checks whether a move eats along a direction by walking the board cell by cell.
+    fn eats_along_direction(board: &Board, side: Side, coord: Coord, dir: Direction) -> bool {
+        let mut next_coord = coord;
+        if let Ok(Ok(Some(next_disk))) = next_coord.step(dir).map(|()| board.get_cell(next_coord)) {
+            if next_disk.get_side() == side.opposite() {
+                while let Ok(Ok(Some(successive_disk))) = next_coord.step(dir).map(|()| board.get_cell(next_coord)) {
+                    if successive_disk.get_side() == side {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Reference implementation of legal moves generation.
+    fn reference_moves(board: &Board, side: Side) -> Vec<(usize, usize)> {
+        let mut moves = Vec::new();
+        for row in 0..board.get_size() {
+            for col in 0..board.get_size() {
+                let coord = Coord::new(row, col);
+                if board.get_cell(coord).unwrap().is_none()
+                    && DIRECTIONS.iter().any(|&dir| eats_along_direction(board, side, coord, dir)) {
+                    moves.push((row, col));
+                }
+            }
+        }
+        moves
+    }
+
+    /// Reference implementation of a move: flips disks direction by direction, cell by cell.
+    fn reference_move(board: &Board, side: Side, coord: Coord) -> Board {
+        let mut board_after_move = *board;
+        for &dir in &DIRECTIONS {
+            if eats_along_direction(board, side, coord, dir) {
+                let mut next_coord = coord;
+                next_coord.step(dir).unwrap();
+                while board.get_disk(next_coord).unwrap().get_side() != side {
+                    board_after_move.flip_disk(next_coord).unwrap();
+                    next_coord.step(dir).unwrap();
+                }
+            }
+        }
+        board_after_move.place_disk(side, coord).unwrap();
+        board_after_move
+    }
+
+    /// Plays random games on a board of the given size, checking every turn against the reference implementation.
+    fn check_random_games(size: usize, games: u32) {
+        for seed in 0..games {
+            let mut rng = ChaChaRng::from_seed(&[seed, size as u32]);
+            let mut turn = Turn::new_game(size).unwrap();
+
+            while let Some(side) = turn.get_state() {
+                let board = *turn.get_board();
+                let moves = reference_moves(&board, side);
+                let legal: Vec<(usize, usize)> = turn.get_legal_moves().iter().map(|coord| coord.get_row_col()).collect();
+                assert_eq!(legal, moves);
+
+                for row in 0..size {
+                    for col in 0..size {
+                        assert_eq!(turn.check_move(Coord::new(row, col)).is_ok(), moves.contains(&(row, col)));
+                    }
+                }
+
+                let (row, col) = moves[rng.gen_range(0, moves.len())];
+                let coord = Coord::new(row, col);
+                let expected = reference_move(&board, side, coord);
+                turn = turn.make_move(coord).unwrap();
+                assert_eq!(*turn.get_board(), expected);
+
+                let cells = expected.get_all_cells();
+                let count = |side: Side| cells.iter().flat_map(|row| row.iter())
+                    .filter(|cell| cell.map(|disk| disk.get_side()) == Some(side)).count() as u16;
+                assert_eq!(turn.get_score(), (count(Side::Dark), count(Side::Light)));
+
+                let expected_state = if !reference_moves(&expected, side.opposite()).is_empty() {
+                    Some(side.opposite())
+                } else if !reference_moves(&expected, side).is_empty() {
+                    Some(side)
+                } else {
+                    None
+                };
+                assert_eq!(turn.get_state(), expected_state);
+            }
+        }
+    }
+
+    #[test]
+    fn bitboard_agrees_with_reference_on_random_games() {
+        check_random_games(DEFAULT_BOARD_SIZE, 200);
+    }
+
+    #[test]
+    fn bitboard_agrees_with_reference_on_all_sizes() {
+        for size in (MIN_BOARD_SIZE / 2)..(MAX_BOARD_SIZE / 2 + 1) {
+            check_random_games(size * 2, 10);
+        }
+    }
+
+    #[test]
+    fn positions_round_trip() {
+        let turn = Turn::from_position("4 -*O- -O*- ---- ---- O").unwrap();
+        assert_eq!(turn.get_size(), 4);
+        assert_eq!(turn.get_score(), (2, 2));
+        assert_eq!(turn.get_state(), Some(Side::Light));
+        assert_eq!(Turn::from_position(&turn.to_position()).unwrap().to_position(), turn.to_position());
+        assert_eq!(Turn::first_turn().to_position(), Turn::from_position(&Turn::first_turn().to_position()).unwrap().to_position());
+        assert!(Turn::from_position("5 -------------------------").is_err());
+        assert!(Turn::from_position("4 -*O-").is_err());
+    }
+
+    /// Builds a turn on an 8x8 board holding only the given disks, with `side` to move.
+    fn position(dark: &[Coord], light: &[Coord], side: Side) -> Turn {
+        let mut cells = vec!['-'; DEFAULT_BOARD_SIZE * DEFAULT_BOARD_SIZE];
+        for coord in dark {
+            cells[coord.get_row() * DEFAULT_BOARD_SIZE + coord.get_col()] = '*';
+        }
+        for coord in light {
+            cells[coord.get_row() * DEFAULT_BOARD_SIZE + coord.get_col()] = 'O';
+        }
+        let cells: String = cells.into_iter().collect();
+        Turn::from_position(&format!("{} {} {}", DEFAULT_BOARD_SIZE, cells, if side == Side::Dark { '*' } else { 'O' })).unwrap()
+    }
+
+    /// The cells met stepping `steps` times from `coord` along `dir`.
+    fn line(coord: Coord, dir: Direction, steps: usize) -> Vec<Coord> {
+        let mut cell = coord;
+        (0..steps).map(|_| {
+            cell.step(dir).unwrap();
+            cell
+        }).collect()
+    }
+
+    /// The direction opposite to `dir`.
+    fn opposite(dir: Direction) -> Direction {
+        let index = DIRECTIONS.iter().position(|&other| other as usize == dir as usize).unwrap();
+        DIRECTIONS[(index + 4) % 8]
+    }
+
+    #[test]
+    fn moves_flip_along_each_direction() {
+        let center = Coord::new(3, 3);
+        for &dir in &DIRECTIONS {
+            // Two Light disks enclosed by a Dark one, and a stray Light disk not enclosed in the opposite direction
+            let cells = line(center, dir, 3);
+            let stray = line(center, opposite(dir), 1);
+            let turn = position(&cells[2..], &[cells[0], cells[1], stray[0]], Side::Dark);
+            let after = turn.make_move(center).unwrap();
+            for &cell in &cells {
+                assert_eq!(after.get_board().get_disk(cell).unwrap().get_side(), Side::Dark, "flipping {:?}", dir);
+            }
+            assert_eq!(after.get_board().get_disk(stray[0]).unwrap().get_side(), Side::Light, "flipping {:?}", dir);
+            assert_eq!(after.get_score(), (4, 1));
+        }
+    }
+
+    #[test]
+    fn moves_flip_in_all_eight_directions_at_once() {
+        let center = Coord::new(3, 3);
+        let light: Vec<Coord> = DIRECTIONS.iter().map(|&dir| line(center, dir, 1)[0]).collect();
+        let dark: Vec<Coord> = DIRECTIONS.iter().map(|&dir| line(center, dir, 2)[1]).collect();
+        let turn = position(&dark, &light, Side::Dark);
+        assert!(turn.check_move(center).is_ok());
+        let after = turn.make_move(center).unwrap();
+        assert_eq!(after.get_score(), (17, 0));
+        assert_eq!(after.get_state(), None);
+    }
+
+    #[test]
+    fn illegal_moves_are_refused() {
+        let turn = Turn::first_turn();
+        match turn.check_move(Coord::new(3, 3)) {
+            Err(ReversiError::CellAlreadyTaken(_)) => {}
+            other => panic!("expected a taken cell, got {:?}", other),
+        }
+        match turn.check_move(Coord::new(0, 0)) {
+            Err(ReversiError::IllegalMove(_)) => {}
+            other => panic!("expected an illegal move, got {:?}", other),
+        }
+        match turn.check_move(Coord::new(DEFAULT_BOARD_SIZE, 0)) {
+            Err(ReversiError::OutOfBoundCoord(_)) => {}
+            other => panic!("expected out of bound coordinates, got {:?}", other),
+        }
+        assert!(turn.make_move(Coord::new(0, 0)).is_err());
+        assert_eq!(turn.get_legal_moves().len(), 4);
+    }
+
+    #[test]
+    fn side_without_moves_passes() {
+        // After c1, Light has no move left but Dark still has b4
+        let turn = Turn::from_position("4 *O-- ---- ---- --O* *").unwrap();
+        let turn = turn.make_move(Coord::new(0, 2)).unwrap();
+        assert_eq!(turn.get_state(), Some(Side::Dark));
+        assert_eq!(turn.get_legal_moves(), vec![Coord::new(3, 1)]);
+
+        // A side to move which cannot play passes straight away
+        let turn = Turn::from_position("4 *O-- ---- ---- --O* O").unwrap();
+        assert_eq!(turn.get_state(), Some(Side::Dark));
+    }
+
+    #[test]
+    fn games_end_when_neither_side_can_move() {
+        // Light is wiped out before the board is full
+        let turn = Turn::from_position("4 *O-- ---- ---- --O* *").unwrap();
+        let turn = turn.make_move(Coord::new(0, 2)).unwrap().make_move(Coord::new(3, 1)).unwrap();
+        assert!(turn.is_endgame());
+        assert_eq!(turn.get_score(), (6, 0));
+        assert_eq!(turn.get_empty_cells(), 10);
+        match turn.make_move(Coord::new(0, 3)) {
+            Err(ReversiError::EndedGame) => {}
+            other => panic!("expected an ended game, got {:?}", other.map(|turn| turn.to_position())),
+        }
+
+        // A full board
+        let turn = Turn::from_position("4 **** **** OOOO OOO- *").unwrap();
+        assert_eq!(turn.get_state(), Some(Side::Dark));
+        let turn = turn.make_move(Coord::new(3, 3)).unwrap();
+        assert!(turn.is_endgame());
+        assert_eq!(turn.get_empty_cells(), 0);
+        assert!(turn.get_legal_moves().is_empty());
+
+        // Empty cells left, but only one side's disks
+        let turn = Turn::from_position("4 **** ---- ---- ---- O").unwrap();
+        assert!(turn.is_endgame());
+    }
+
+    #[test]
+    fn moves_conserve_disks() {
+        for seed in 0..50 {
+            let mut rng = ChaChaRng::from_seed(&[seed]);
+            let mut turn = Turn::first_turn();
+            while let Some(side) = turn.get_state() {
+                let moves = turn.get_legal_moves();
+                let after = turn.make_move(moves[rng.gen_range(0, moves.len())]).unwrap();
+                let own = |turn: &Turn| if side == Side::Dark { turn.get_score().0 } else { turn.get_score().1 };
+                let flipped = own(&after) - own(&turn) - 1;
+                // Every move places one disk and flips at least one of the opponent's
+                assert!(flipped >= 1);
+                assert_eq!(after.get_tempo(), turn.get_tempo() + 1);
+                assert_eq!(after.get_empty_cells() + after.get_tempo(), 64);
+                turn = after;
+            }
+            assert!(turn.get_legal_moves().is_empty());
+        }
+    }
+
+    /// Counts the move sequences of `depth` moves, or less if the game ends before.
+    fn perft(turn: &Turn, depth: u32) -> u64 {
+        if depth == 0 || turn.is_endgame() {
+            return 1;
+        }
+        turn.get_legal_moves().into_iter().map(|coord| perft(&turn.make_move(coord).unwrap(), depth - 1)).sum()
+    }
+
+    #[test]
+    fn perft_from_the_initial_position() {
+        // No game can end nor any side pass in the first eight moves
+        let counts = [1, 4, 12, 56, 244, 1396, 8200, 55092, 390216];
+        for (depth, &count) in counts.iter().enumerate() {
+            assert_eq!(perft(&Turn::first_turn(), depth as u32), count, "at depth {}", depth);
+        }
+    }
+}
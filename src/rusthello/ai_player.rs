@@ -1,19 +1,44 @@
 //! Provides `game::IsPlayer<::OtherAction>` types.
 
 use reversi;
-use reversi::{board, turn, game};
+use reversi::{bitboard, board, turn, game};
 use reversi::board::Coord;
 use ::{Result, Action};
-use std::cmp::Ordering;
+use std::cmp::{self, Ordering};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, Sender};
 use std::thread;
+use std::time::Duration;
+use search::Searcher;
+use endgame;
+use evaluation::Weights;
+use book::Book;
+use clock::Clocks;
 use rand::distributions::{Range, Sample};
-use rand::ChaChaRng;
+use rand::{ChaChaRng, SeedableRng};
 
 const RANDOMNESS: f64 = 0.05f64;
 const WEAK:   u32 = 100;
 const MEDIUM: u32 = 1000;
 const STRONG: u32 = 10000;
 
+/// On time, the AIs search for at most this long, in milliseconds, and less if their clock is short.
+const WEAK_TIME:   u64 = 100;
+const MEDIUM_TIME: u64 = 1000;
+const STRONG_TIME: u64 = 10000;
+
+/// On time, the strong AI only solves the endgame if its clock leaves it at least this long for the move, in milliseconds.
+const ENDGAME_TIME: u64 = 2000;
+
+/// Number of threads evaluating moves, unless set otherwise.
+const THREADS: usize = 4;
+
+/// Seeds the choice of a book move apart from the evaluations of moves, which are seeded by the cells' indexes.
+const BOOK_SALT: u32 = !0;
+
+/// The strong AI plays perfectly from this many empty cells on.
+pub const STRONG_ENDGAME_EMPTIES: u16 = 12;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Score {
     Running(f64),
@@ -53,91 +78,288 @@ impl Ord for Score {
     }
 }
 
-pub enum AiPlayer {
+/// How hard an `AiPlayer` thinks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strength {
     Weak,
     Medium,
     Strong,
+    /// Searches with alpha-beta pruning for the given time per move.
+    Timed(Duration),
+}
+
+/// An evaluation of one candidate move, to be run by a worker.
+struct Job {
+    index: usize,
+    turn: turn::Turn,
+    comps: u32,
+    weights: Weights,
+    rng: ChaChaRng,
+    randomness: f64,
+    results: Sender<(usize, Result<Score>)>,
+}
+
+/// A fixed set of threads evaluating candidate moves.
+/// The threads stop when the pool is dropped.
+/// Players start their pool on their first evaluation, so that players which never evaluate moves do not start threads.
+struct WorkerPool {
+    jobs: Sender<Job>,
+}
+
+impl WorkerPool {
+    fn new(threads: usize) -> WorkerPool {
+        let (jobs, receiver) = channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..threads {
+            let receiver = receiver.clone();
+            thread::spawn(move || {
+                loop {
+                    let job = receiver.lock().expect("A worker panicked").recv();
+                    let mut job = match job {
+                        Ok(job) => job,
+                        Err(_) => break, // The pool is gone
+                    };
+                    let score = AiPlayer::ai_eval(&job.turn, job.comps, &job.weights, &mut job.rng, job.randomness);
+                    // Whoever asked may have given up already: that is not an error
+                    let _ = job.results.send((job.index, score));
+                }
+            });
+        }
+        WorkerPool {
+            jobs: jobs,
+        }
+    }
+}
+
+/// A computer player.
+/// Given the same seed, it always plays the same moves from the same positions, whatever the number of threads.
+pub struct AiPlayer {
+    strength: Strength,
+    seed: u32,
+    randomness: f64,
+    weights: Weights,
+    book: Arc<Book>,
+    /// The clocks of the match, if it is played on time.
+    clocks: Option<Arc<Mutex<Clocks>>>,
+    threads: usize,
+    /// The workers evaluating moves, once started (see `jobs`).
+    pool: Mutex<Option<WorkerPool>>,
 }
 
 impl game::IsPlayer<::OtherAction> for AiPlayer {
-    /// Calls `find_best_move` with suitable parameters
+    /// Plays from the opening book if possible, otherwise looks for the best move with suitable parameters.
+    /// On time, every search is bounded by the player's clock.
     fn make_move(&self, turn: &turn::Turn) -> Result<Action> {
-        Ok(game::PlayerAction::Move(try!(match *self {
-            AiPlayer::Weak => AiPlayer::find_best_move(turn, WEAK),
-            AiPlayer::Medium => AiPlayer::find_best_move(turn, MEDIUM),
-            AiPlayer::Strong => AiPlayer::find_best_move(turn, STRONG),
+        if let Some(coord) = self.book.choose(turn, &mut self.rng(turn, BOOK_SALT)) {
+            return Ok(game::PlayerAction::Move(coord));
+        }
+        Ok(game::PlayerAction::Move(try!(match self.strength {
+            Strength::Weak => self.choose_move(turn, WEAK, WEAK_TIME),
+            Strength::Medium => self.choose_move(turn, MEDIUM, MEDIUM_TIME),
+            Strength::Strong => {
+                // Solving the endgame takes no account of the clock, so it is only tried with time to spare
+                let endgame_time = Duration::from_millis(ENDGAME_TIME);
+                let solution = if self.clocks.is_none() || self.time_limit(turn, endgame_time) >= endgame_time {
+                    try!(endgame::Solver::new(STRONG_ENDGAME_EMPTIES).solve(turn))
+                } else {
+                    None
+                };
+                match solution {
+                    Some(solution) => Ok(solution.coord),
+                    None => self.choose_move(turn, STRONG, STRONG_TIME),
+                }
+            }
+            Strength::Timed(limit) => Searcher::new(self.time_limit(turn, limit), self.weights).find_best_move(turn),
         })))
     }
 }
 
 impl AiPlayer {
-    /// Find best moves among the legal ones.
-    /// Each possibility is evaluated by a method depending on the value of `self` and confronted with the others.
-    pub fn find_best_move(turn: &turn::Turn, comps: u32) -> Result<board::Coord> {
+    /// Creates a player of the given strength, whose random choices are drawn from `seed`.
+    pub fn new(strength: Strength, seed: u32) -> AiPlayer {
+        AiPlayer {
+            strength: strength,
+            seed: seed,
+            randomness: RANDOMNESS,
+            weights: Weights::default(),
+            book: Arc::new(Book::default()),
+            clocks: None,
+            threads: THREADS,
+            pool: Mutex::new(None),
+        }
+    }
+
+    /// Sets by how much (as a fraction) evaluations are randomly perturbed. Zero makes the player fully predictable.
+    pub fn with_randomness(mut self, randomness: f64) -> AiPlayer {
+        self.randomness = randomness.abs();
+        self
+    }
+
+    /// Sets the weights of the evaluation function.
+    pub fn with_weights(mut self, weights: Weights) -> AiPlayer {
+        self.weights = weights;
+        self
+    }
+
+    /// Sets the opening book, which can be shared between players.
+    pub fn with_book(mut self, book: Arc<Book>) -> AiPlayer {
+        self.book = book;
+        self
+    }
+
+    /// Changes the seed of the random choices, keeping the workers already started.
+    pub fn reseed(&mut self, seed: u32) {
+        self.seed = seed;
+    }
+
+    /// Changes how hard the player thinks, keeping the workers already started.
+    pub fn set_strength(&mut self, strength: Strength) {
+        self.strength = strength;
+    }
+
+    /// Sets the clocks of the match: players then never think longer than their clock allows.
+    pub fn with_clocks(mut self, clocks: Arc<Mutex<Clocks>>) -> AiPlayer {
+        self.clocks = Some(clocks);
+        self
+    }
+
+    /// The time to think about a move: the time per move of a timed player, cut down to its budget if playing on time.
+    fn time_limit(&self, turn: &turn::Turn, limit: Duration) -> Duration {
+        match (&self.clocks, turn.get_state()) {
+            (&Some(ref clocks), Some(side)) => {
+                let clocks = clocks.lock().expect("Could not read the clocks");
+                cmp::min(limit, clocks.budget(side, turn.get_empty_cells()))
+            }
+            _ => limit,
+        }
+    }
+
+    /// Sets how many threads evaluate the candidate moves (at least one).
+    #[cfg(test)]
+    pub fn with_threads(mut self, threads: usize) -> AiPlayer {
+        self.threads = cmp::max(threads, 1);
+        self.pool = Mutex::new(None);
+        self
+    }
+
+    /// Where to send evaluation jobs, starting the pool of workers if it is not yet running.
+    fn jobs(&self) -> Sender<Job> {
+        let mut pool = self.pool.lock().expect("Could not use the workers");
+        if pool.is_none() {
+            *pool = Some(WorkerPool::new(self.threads));
+        }
+        pool.as_ref().expect("The workers were just started").jobs.clone()
+    }
+
+    /// Scores all the legal moves, the best one first.
+    /// Each possibility is evaluated by a worker of the pool and confronted with the others.
+    /// Scores are from Light's point of view, as for the evaluation function.
+    pub fn find_best_move(&self, turn: &turn::Turn, comps: u32) -> Result<Vec<(Coord, Score)>> {
 
         // If everything is alright, turn shouldn't be ended
         let side = try!(turn.get_state().ok_or(reversi::ReversiError::EndedGame));
 
         // Finds all possible legal moves and records their coordinates
-        let mut moves: Vec<Coord> = Vec::new();
-        for row in 0..board::BOARD_SIZE {
-            for col in 0..board::BOARD_SIZE {
-                let coord = board::Coord::new(row, col);
-                if turn.check_move(coord).is_ok() {
-                    moves.push(coord);
-                }
-            }
+        let moves: Vec<Coord> = turn.get_legal_moves();
+        let num_moves = moves.len();
+
+        // Each move has to be evaluated in order to rank it
+        let (results, receiver) = channel();
+        let jobs = self.jobs();
+        for (index, &coord) in moves.iter().enumerate() {
+            jobs.send(Job {
+                index: index,
+                turn: try!(turn.make_move(coord)),
+                comps: comps / num_moves as u32,
+                weights: self.weights,
+                rng: self.rng(turn, bitboard::index(coord)),
+                randomness: self.randomness,
+                results: results.clone(),
+            }).expect("The workers are gone");
         }
 
-        match moves.len() {
-            0 => unreachable!("Game is not ended!"), // Game can't be ended
-            1 => Ok(moves[0]), // If there is only one possible move, there's no point in evaluating it.
-            num_moves @ _ => { // Each move has to be evaluated in order to find the best one
-                let mut threadjoins = Vec::new();
-                while let Some(coord) = moves.pop() {
-                    let turn_after_move = try!(turn.make_move(coord));
-                    threadjoins.push(thread::spawn(move || {
-                        Ok((coord, try!(AiPlayer::ai_eval(&turn_after_move, comps / num_moves as u32))))
-                    }));
-                }
+        // Results come in any order, so they are put back in the order of the moves
+        let mut scores: Vec<Option<Score>> = vec![None; num_moves];
+        for _ in 0..num_moves {
+            let (index, score) = receiver.recv().expect("Could not receive answer");
+            scores[index] = Some(try!(score));
+        }
 
-                let mut coord_eval_moves: Vec<(board::Coord, Score)> = Vec::new();
-                for threadjoin in threadjoins {
-                    coord_eval_moves.push(try!(threadjoin.join().expect("Could not receive answer")));
-                }
+        // Sort moves (lowest or highest scores first depending on turn.state), breaking ties by the moves' order
+        let mut score_index: Vec<(Score, usize)> = scores.into_iter().enumerate()
+            .map(|(i, score)| (score.expect("Every move was evaluated"), i)).collect();
+        match side {
+            reversi::Side::Dark => score_index.sort(),
+            reversi::Side::Light => score_index.sort_by(|a, b| b.cmp(a)),
+        }
+        Ok(score_index.into_iter().map(|(score, i)| (moves[i], score)).collect())
+    }
 
-                // Find best move (min or max depending on turn.state). Weird tricks with iterators and tuples
-                let score_index_iter = coord_eval_moves.clone().into_iter().enumerate()
-                    .map( move |(i, coord_score)| (coord_score.1, i) );
-                let best_score_index = match side {
-                    reversi::Side::Dark  => score_index_iter.min().expect("Why should this fail?"),
-                    reversi::Side::Light => score_index_iter.max().expect("Why should this fail?"),
-                };
-                Ok(coord_eval_moves[best_score_index.1].0)
+    /// Scores all the legal moves as `find_best_move` does, with as many computations as the player's strength allows.
+    /// Timed players use the computations of strong ones.
+    pub fn score_moves(&self, turn: &turn::Turn) -> Result<Vec<(Coord, Score)>> {
+        self.find_best_move(turn, match self.strength {
+            Strength::Weak => WEAK,
+            Strength::Medium => MEDIUM,
+            Strength::Strong | Strength::Timed(_) => STRONG,
+        })
+    }
+
+    /// Chooses the best move, without evaluating it if it is the only legal one.
+    /// Moves are evaluated with `comps` computations, or on time by a search of at most `millis` milliseconds.
+    fn choose_move(&self, turn: &turn::Turn, comps: u32, millis: u64) -> Result<board::Coord> {
+        let moves = turn.get_legal_moves();
+        if moves.len() == 1 {
+            return Ok(moves[0]);
+        }
+        if self.clocks.is_some() {
+            return Searcher::new(self.time_limit(turn, Duration::from_millis(millis)), self.weights).find_best_move(turn);
+        }
+        Ok(try!(self.find_best_move(turn, comps))[0].0)
+    }
+
+    /// The random generator used in a position, which depends only on the seed, the position and `salt`:
+    /// the index of the move to evaluate, or `BOOK_SALT` to choose from the book.
+    fn rng(&self, turn: &turn::Turn, salt: u32) -> ChaChaRng {
+        let dark = turn.get_board().get_bits(reversi::Side::Dark);
+        let light = turn.get_board().get_bits(reversi::Side::Light);
+        // The generator is keyed on its first 8 words only, so both boards are hashed into the 6 left,
+        // by three hashes starting from different states
+        let mut hashes = [1u64, 2, 3];
+        for &word in dark.words().iter().chain(light.words().iter()) {
+            for hash in &mut hashes {
+                *hash = mix(*hash ^ word);
             }
         }
+        let mut seed = vec![self.seed, salt];
+        for &hash in &hashes {
+            seed.push(hash as u32);
+            seed.push((hash >> 32) as u32);
+        }
+        ChaChaRng::from_seed(&seed)
     }
 
-    fn ai_eval(turn: &turn::Turn, comps: u32) -> Result<Score> {
+    fn ai_eval(turn: &turn::Turn, comps: u32, weights: &Weights, rng: &mut ChaChaRng, randomness: f64) -> Result<Score> {
         if turn.is_endgame() {
             Ok(Score::Ended(turn.get_score_diff()))
         } else {
-            let mut score = try!(AiPlayer::ai_eval_with_leftover(turn, comps)).0;
+            let mut score = try!(AiPlayer::ai_eval_with_leftover(turn, comps, weights)).0;
             // Add some randomness
-            let mut between = Range::new(-RANDOMNESS, RANDOMNESS);
-            let mut rng = ChaChaRng::new_unseeded();
-            score = match score {
-                Score::Running(val) => {
-                    Score::Running(val * (1.0 + between.sample(&mut rng)))
-                }
-                _ => score,
-            };
+            if randomness > 0f64 {
+                let mut between = Range::new(-randomness, randomness);
+                score = match score {
+                    Score::Running(val) => {
+                        Score::Running(val * (1.0 + between.sample(rng)))
+                    }
+                    _ => score,
+                };
+            }
             // Done, return
             Ok(score)
         }
     }
 
-    fn ai_eval_with_leftover(turn: &turn::Turn, comps: u32) -> Result<(Score, u32)> {
+    fn ai_eval_with_leftover(turn: &turn::Turn, comps: u32, weights: &Weights) -> Result<(Score, u32)> {
 
         // If everything is alright, turn shouldn't be ended
         // assert!(!this_turn.is_endgame());
@@ -146,15 +368,7 @@ impl AiPlayer {
         let mut moves: Vec<Coord>;
         let mut turn = turn.clone();
         loop {
-            moves = Vec::new();
-            for row in 0..board::BOARD_SIZE {
-                for col in 0..board::BOARD_SIZE {
-                    let coord = board::Coord::new(row, col);
-                    if turn.check_move(coord).is_ok() {
-                        moves.push(coord);
-                    }
-                }
-            }
+            moves = turn.get_legal_moves();
             match moves.len() {
                 0 => unreachable!("Endgame should have been detected earlier: here it's a waste of computations!"),
                 1 => {
@@ -179,10 +393,10 @@ impl AiPlayer {
             scores.push(
                 match turn_after_move.get_state() {
                     None => Score::Ended(turn_after_move.get_score_diff()),
-                    Some(_) if leftover < turns_left => Score::Running(try!(AiPlayer::heavy_eval(&turn_after_move))),
+                    Some(_) if leftover < turns_left => Score::Running(try!(weights.evaluate(&turn_after_move))),
                     _ => {
                         let new_comps = leftover / turns_left; // since leftover >= turns_left, then new_comps >= 1
-                        let new_score_leftover = try!(AiPlayer::ai_eval_with_leftover(&turn_after_move, new_comps));
+                        let new_score_leftover = try!(AiPlayer::ai_eval_with_leftover(&turn_after_move, new_comps, weights));
                         leftover += new_score_leftover.1;
                         leftover -= new_comps; // since leftover >= turns_left, leftover - newcomps >= 0
                         new_score_leftover.0
@@ -201,7 +415,9 @@ impl AiPlayer {
         ))
     }
 
-    fn heavy_eval(turn: &turn::Turn) -> Result<f64> {
+    /// Evaluates a position by looking at corners and the cells next to them.
+    /// Returns a value between -1 (good for Dark) and 1 (good for Light).
+    pub fn heavy_eval(turn: &turn::Turn) -> Result<f64> {
         // Weights
         const CORNER_BONUS: u16 = 45;
         const ODD_CORNER_MALUS: u16 = 25;
@@ -210,12 +426,16 @@ impl AiPlayer {
         const EVEN_BONUS: u16 = 4; // x2
         // ------------------------ Sum = 100
 
-        // Special cells
-        let sides: [(Coord,Coord,Coord,Coord,Coord,Coord,Coord); 4]
-            =  [(Coord::new(0, 0), Coord::new(0, 1), Coord::new(1, 1), Coord::new(0, 2), Coord::new(2, 2), Coord::new(1, 0), Coord::new(2, 0)),   /* NW corner */
-                (Coord::new(0, 7), Coord::new(1, 7), Coord::new(1, 6), Coord::new(2, 7), Coord::new(2, 5), Coord::new(0, 6), Coord::new(0, 5)),   /* NE corner */
-                (Coord::new(7, 0), Coord::new(6, 0), Coord::new(6, 1), Coord::new(5, 0), Coord::new(5, 2), Coord::new(7, 1), Coord::new(7, 2)),   /* SW corner */
-                (Coord::new(7, 7), Coord::new(6, 7), Coord::new(6, 6), Coord::new(5, 7), Coord::new(5, 5), Coord::new(7, 6), Coord::new(7, 5))];  /* SE corner */
+        // Special cells: for each corner, the cells at given offsets from it towards the inside of the board
+        let last = turn.get_size() as isize - 1;
+        let near = |(row, col, row_dir, col_dir): (isize, isize, isize, isize), (i, j): (isize, isize)| {
+            Coord::new((row + row_dir * i) as usize, (col + col_dir * j) as usize)
+        };
+        let mut sides: Vec<(Coord,Coord,Coord,Coord,Coord,Coord,Coord)> = Vec::with_capacity(4);
+        for &corner in &[(0, 0, 1, 1), (0, last, 1, -1), (last, 0, -1, 1), (last, last, -1, -1)] {   /* NW, NE, SW, SE corners */
+            sides.push((near(corner, (0, 0)), near(corner, (0, 1)), near(corner, (1, 1)), near(corner, (0, 2)),
+                        near(corner, (2, 2)), near(corner, (1, 0)), near(corner, (2, 0))));
+        }
 
         let mut score_light: u16 = 1;
         let mut score_dark: u16 = 1;
@@ -274,3 +494,49 @@ impl AiPlayer {
         Ok((score_light as f64 - score_dark as f64) / (score_dark + score_light) as f64)
     }
 }
+
+/// Scrambles the bits of a word, as in the finalizer of SplitMix64: any change to the input changes about half of the output.
+fn mix(word: u64) -> u64 {
+    let word = (word ^ (word >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    let word = (word ^ (word >> 27)).wrapping_mul(0x94d049bb133111eb);
+    word ^ (word >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use reversi::turn::Turn;
+    use super::{AiPlayer, Strength, WEAK};
+
+    /// Plays a whole game between two weak players and lists its moves.
+    fn play(dark: &AiPlayer, light: &AiPlayer) -> Vec<(usize, usize)> {
+        let mut turn = Turn::first_turn();
+        let mut moves = Vec::new();
+        while let Some(side) = turn.get_state() {
+            let player = if side == ::reversi::Side::Dark { dark } else { light };
+            let coord = player.find_best_move(&turn, WEAK).unwrap()[0].0;
+            moves.push(coord.get_row_col());
+            turn = turn.make_move(coord).unwrap();
+        }
+        moves
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        let game = play(&AiPlayer::new(Strength::Weak, 7), &AiPlayer::new(Strength::Weak, 8));
+        let again = play(&AiPlayer::new(Strength::Weak, 7).with_threads(1), &AiPlayer::new(Strength::Weak, 8).with_threads(3));
+        assert_eq!(game, again);
+    }
+
+    #[test]
+    fn both_sides_make_the_random_choices() {
+        let player = AiPlayer::new(Strength::Weak, 7);
+        let start = Turn::from_position("4 -*O- -O*- ---- ---- *").unwrap();
+        let more_light = Turn::from_position("4 -*O- -O*- ---- ---O *").unwrap();
+        let more_dark = Turn::from_position("4 -*O- -O*- ---- ---* *").unwrap();
+        let draw = |turn: &Turn| player.rng(turn, 0).next_u32();
+        assert!(draw(&start) != draw(&more_light));
+        assert!(draw(&start) != draw(&more_dark));
+        assert_eq!(draw(&start), player.rng(&start.clone(), 0).next_u32());
+    }
+}
@@ -0,0 +1,268 @@
+//! Opening book: statistics about the first moves of recorded games, which the AIs follow before searching.
+//!
+//! The book file holds one line per move sequence from the standard start, with the games which went through it:
+//! `f5d6c3 12 3 9` means 12 games won by Dark, 3 ties and 9 games won by Light after `f5 d6 c3`.
+//! A line `variety V` sets how far from the best-scoring move (as a fraction of games) the AIs may stray,
+//! so that they do not always play the same line. Empty lines and lines starting with `#` are ignored.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use rand::Rng;
+use reversi::{self, Side};
+use reversi::board::Coord;
+use reversi::record::Record;
+use reversi::turn::Turn;
+
+/// The file the book is read from and the builder writes it to.
+pub const BOOK_FILE: &'static str = "rusthello_book.txt";
+
+/// Default variety: moves scoring at most this much worse than the best one may be played.
+const VARIETY: f64 = 0.05;
+
+/// Only this many moves from the start are recorded by the builder.
+const BOOK_DEPTH: usize = 16;
+
+/// Moves played in fewer games are not trusted.
+const MIN_GAMES: u32 = 2;
+
+/// Results of the games which went through a move.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Stats {
+    pub dark_wins: u32,
+    pub draws: u32,
+    pub light_wins: u32,
+}
+
+impl Stats {
+    fn games(&self) -> u32 {
+        self.dark_wins + self.draws + self.light_wins
+    }
+
+    /// The fraction of points won by a side, counting a tie as half a win.
+    fn score(&self, side: Side) -> f64 {
+        let wins = match side {
+            Side::Dark => self.dark_wins,
+            Side::Light => self.light_wins,
+        };
+        (wins as f64 + self.draws as f64 / 2f64) / self.games() as f64
+    }
+
+    fn add(&mut self, other: Stats) {
+        self.dark_wins += other.dark_wins;
+        self.draws += other.draws;
+        self.light_wins += other.light_wins;
+    }
+}
+
+/// An opening book.
+#[derive(Debug, Clone)]
+pub struct Book {
+    /// How much worse than the best move (as a fraction of games) a move may score and still be played.
+    pub variety: f64,
+    /// The statistics of the move sequences, as written in the file.
+    lines: Vec<(Vec<Coord>, Stats)>,
+    /// The moves known from each position, whatever the sequence leading there.
+    moves: HashMap<String, Vec<(Coord, Stats)>>,
+}
+
+impl Default for Book {
+    fn default() -> Book {
+        Book {
+            variety: VARIETY,
+            lines: Vec::new(),
+            moves: HashMap::new(),
+        }
+    }
+}
+
+impl Book {
+    /// Reads a book from a file, in the format described above.
+    pub fn load(path: &str) -> io::Result<Book> {
+        let mut text = String::new();
+        try!(File::open(path).and_then(|mut file| file.read_to_string(&mut text)));
+
+        let mut book = Book::default();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words[0] == "variety" && words.len() == 2 {
+                book.variety = try!(words[1].parse().map_err(|_| invalid(format!("invalid variety '{}'", words[1]))));
+                continue;
+            }
+            let counts: Vec<u32> = words[1..].iter().filter_map(|word| word.parse().ok()).collect();
+            if counts.len() != 3 || words.len() != 4 {
+                return Err(invalid(format!("invalid line '{}'", line)));
+            }
+            let record = try!(Record::parse(words[0]).map_err(|_| invalid(format!("invalid moves '{}'", words[0]))));
+            try!(book.add(record.moves, Stats { dark_wins: counts[0], draws: counts[1], light_wins: counts[2] })
+                .map_err(|_| invalid(format!("invalid moves '{}'", words[0]))));
+        }
+        Ok(book)
+    }
+
+    /// Writes the book to a file, in the format read by `load`.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut file = try!(File::create(path));
+        try!(writeln!(file, "# RUSThello opening book: moves, Dark wins, ties, Light wins"));
+        try!(writeln!(file, "variety {}", self.variety));
+        for &(ref moves, stats) in &self.lines {
+            let transcript: String = moves.iter().map(|&coord| coord.to_string()).collect();
+            try!(writeln!(file, "{} {} {} {}", transcript, stats.dark_wins, stats.draws, stats.light_wins));
+        }
+        Ok(())
+    }
+
+    /// Adds the statistics of a move sequence from the standard start.
+    fn add(&mut self, moves: Vec<Coord>, stats: Stats) -> reversi::Result<()> {
+        let (&last, before) = try!(moves.split_last().ok_or(reversi::ReversiError::InvalidRecord));
+        let mut turn = Turn::first_turn();
+        for &coord in before {
+            turn = try!(turn.make_move(coord));
+        }
+        try!(turn.check_move(last));
+
+        let known = self.moves.entry(turn.to_position()).or_insert_with(Vec::new);
+        match known.iter().position(|&(coord, _)| coord == last) {
+            Some(index) => known[index].1.add(stats),
+            None => known.push((last, stats)),
+        }
+        self.lines.push((moves, stats));
+        Ok(())
+    }
+
+    /// Chooses a move for the given turn among the good enough ones in the book, if there are any.
+    pub fn choose<R: Rng>(&self, turn: &Turn, rng: &mut R) -> Option<Coord> {
+        let side = match turn.get_state() {
+            Some(side) => side,
+            None => return None,
+        };
+        let known: Vec<(Coord, f64)> = match self.moves.get(&turn.to_position()) {
+            Some(known) => known.iter().filter(|&&(_, stats)| stats.games() >= MIN_GAMES)
+                .map(|&(coord, stats)| (coord, stats.score(side))).collect(),
+            None => return None,
+        };
+        let best = known.iter().map(|&(_, score)| score).fold(0f64, f64::max);
+        let candidates: Vec<Coord> = known.into_iter().filter(|&(_, score)| score >= best - self.variety)
+            .map(|(coord, _)| coord).collect();
+        if candidates.is_empty() {
+            None
+        } else {
+            Some(candidates[rng.gen_range(0, candidates.len())])
+        }
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Builds a book from files of finished games, one record per line (transcripts or GGF), and writes it to `path`.
+/// The variety is kept from the book already there, if any. Returns the number of games read.
+pub fn build(games: &[String], path: &str) -> io::Result<u32> {
+    if games.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "no files of games"));
+    }
+    let mut book = Book::default();
+    if let Ok(old_book) = Book::load(path) {
+        book.variety = old_book.variety;
+    }
+
+    // The statistics of every sequence, from the shortest to the longest
+    let mut sequences: HashMap<Vec<Coord>, Stats> = HashMap::new();
+    let mut read = 0;
+    for game_file in games {
+        let mut text = String::new();
+        try!(File::open(game_file).and_then(|mut file| file.read_to_string(&mut text)));
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let record = match Record::parse(line) {
+                Ok(ref record) if record.has_standard_start() => record.clone(),
+                _ => continue,
+            };
+            let last = try!(record.turns().map_err(|err| invalid(err.to_string())))
+                .pop().expect("There is a first turn");
+            if !last.is_endgame() {
+                continue;
+            }
+            let result = match last.get_score_diff() {
+                0 => Stats { draws: 1, ..Stats::default() },
+                diff if diff < 0 => Stats { dark_wins: 1, ..Stats::default() },
+                _ => Stats { light_wins: 1, ..Stats::default() },
+            };
+            for length in 1..(record.moves.len().min(BOOK_DEPTH) + 1) {
+                sequences.entry(record.moves[..length].to_vec()).or_insert_with(Stats::default).add(result);
+            }
+            read += 1;
+        }
+    }
+
+    let mut sequences: Vec<(Vec<Coord>, Stats)> = sequences.into_iter().collect();
+    sequences.sort_by_key(|&(ref moves, _)| (moves.len(), moves.iter().map(|&coord| coord.get_row_col()).collect::<Vec<_>>()));
+    for (moves, stats) in sequences {
+        try!(book.add(moves, stats).map_err(|err| invalid(err.to_string())));
+    }
+    try!(book.save(path));
+    Ok(read)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::process;
+    use rand::{ChaChaRng, SeedableRng};
+    use reversi::board::Coord;
+    use reversi::turn::Turn;
+    use super::*;
+
+    /// Loads a book written with the given text, from a file only this test run uses.
+    fn load(name: &str, text: &str) -> Book {
+        let path = env::temp_dir().join(format!("rusthello_book_{}_{}.txt", name, process::id()));
+        let path = path.to_str().unwrap();
+        File::create(path).unwrap().write_all(text.as_bytes()).unwrap();
+        let book = Book::load(path).unwrap();
+        fs::remove_file(path).unwrap();
+        book
+    }
+
+    /// All the moves chosen from the first turn over many draws, in the order of the cells.
+    fn choices(book: &Book) -> Vec<Coord> {
+        let mut chosen: Vec<Coord> = (0..100).filter_map(|seed| book.choose(&Turn::first_turn(), &mut ChaChaRng::from_seed(&[seed]))).collect();
+        chosen.sort_by_key(|coord| coord.get_row_col());
+        chosen.dedup();
+        chosen
+    }
+
+    // Dark wins all games after f5 and most after e6, half of them after d3; c4 was played only once
+    const GAMES: &'static str = "# test book\nf5 10 0 0\ne6 8 0 2\nd3 5 0 5\nc4 1 0 0\n";
+
+    #[test]
+    fn only_the_best_moves_are_chosen() {
+        let book = load("best", &format!("variety 0.1\n{}", GAMES));
+        assert_eq!(book.variety, 0.1);
+        assert_eq!(choices(&book), vec![Coord::new(4, 5)]);
+    }
+
+    #[test]
+    fn variety_lets_worse_moves_be_chosen() {
+        let book = load("variety", &format!("variety 0.25\n{}", GAMES));
+        assert_eq!(choices(&book), vec![Coord::new(4, 5), Coord::new(5, 4)]);
+    }
+
+    #[test]
+    fn moves_played_in_too_few_games_are_not_chosen() {
+        // c4 would be the best move, but it was played only once
+        let book = load("few", "variety 0.1\nc4 1 0 0\nd3 5 0 5\n");
+        assert_eq!(choices(&book), vec![Coord::new(2, 3)]);
+        let book = load("none", "c4 1 0 0\n");
+        assert!(book.choose(&Turn::first_turn(), &mut ChaChaRng::from_seed(&[0])).is_none());
+    }
+
+    #[test]
+    fn positions_out_of_the_book_have_no_moves() {
+        let book = load("out", GAMES);
+        let turn = Turn::first_turn().make_move(Coord::new(4, 5)).unwrap();
+        assert!(book.choose(&turn, &mut ChaChaRng::from_seed(&[0])).is_none());
+        assert!(Book::default().choose(&Turn::first_turn(), &mut ChaChaRng::from_seed(&[0])).is_none());
+    }
+}
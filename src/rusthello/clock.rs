@@ -0,0 +1,189 @@
+//! Game clocks: each side has some time for the whole game, plus an increment for every move it makes.
+//! A side whose clock runs out loses on time.
+
+use std::{cmp, fmt};
+use std::time::{Duration, Instant};
+use reversi::Side;
+
+/// Part of the remaining time kept as a safety margin by the AI.
+const SAFETY_MARGIN: u32 = 20;
+
+/// The time each side has: `base` for the whole game, plus `increment` for every move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+}
+
+impl TimeControl {
+    /// Parses a time control written as `MINUTES+SECONDS`, e.g. `5+3` for five minutes plus three seconds per move.
+    pub fn parse(text: &str) -> Option<TimeControl> {
+        let mut parts = text.splitn(2, '+');
+        let minutes = parts.next().and_then(|minutes| minutes.trim().parse::<u64>().ok());
+        let seconds = parts.next().and_then(|seconds| seconds.trim().parse::<u64>().ok());
+        match (minutes, seconds) {
+            (Some(minutes), Some(seconds)) if minutes > 0 || seconds > 0 => Some(TimeControl {
+                base: Duration::from_secs(minutes * 60),
+                increment: Duration::from_secs(seconds),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TimeControl {
+    /// Writes the time control as `MINUTES+SECONDS`, as read by `parse`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}+{}", self.base.as_secs() / 60, self.increment.as_secs())
+    }
+}
+
+/// The clocks of both sides. At most one of them runs at any time.
+#[derive(Debug, Clone)]
+pub struct Clocks {
+    dark: Duration,
+    light: Duration,
+    increment: Duration,
+    /// The side whose clock is running, and since when.
+    running: Option<(Side, Instant)>,
+    /// Whether each side made its first move, whose increment is given in advance.
+    dark_moved: bool,
+    light_moved: bool,
+}
+
+impl Clocks {
+    pub fn new(control: TimeControl) -> Clocks {
+        Clocks {
+            // The first move gets its increment before it is played, so that there is time to play it
+            dark: control.base + control.increment,
+            light: control.base + control.increment,
+            increment: control.increment,
+            running: None,
+            dark_moved: false,
+            light_moved: false,
+        }
+    }
+
+    /// The time left to a side, counting the time elapsed if its clock is running.
+    pub fn remaining(&self, side: Side) -> Duration {
+        let left = match side {
+            Side::Dark => self.dark,
+            Side::Light => self.light,
+        };
+        match self.running {
+            Some((running_side, since)) if running_side == side => left.checked_sub(since.elapsed()).unwrap_or(Duration::from_secs(0)),
+            _ => left,
+        }
+    }
+
+    /// Returns true if a side ran out of time.
+    pub fn is_flagged(&self, side: Side) -> bool {
+        self.remaining(side) == Duration::from_secs(0)
+    }
+
+    /// Starts the clock of a side, stopping the other one.
+    pub fn start(&mut self, side: Side) {
+        self.stop(false);
+        self.running = Some((side, Instant::now()));
+    }
+
+    /// Stops the running clock, if any, adding the increment if its side made a move other than its first one.
+    pub fn stop(&mut self, moved: bool) {
+        if let Some((side, _)) = self.running {
+            let mut left = self.remaining(side);
+            let first_move = match side {
+                Side::Dark => !self.dark_moved,
+                Side::Light => !self.light_moved,
+            };
+            // Once flagged, a side gets no more time
+            if moved && !first_move && left > Duration::from_secs(0) {
+                left += self.increment;
+            }
+            match side {
+                Side::Dark => {
+                    self.dark = left;
+                    self.dark_moved |= moved;
+                }
+                Side::Light => {
+                    self.light = left;
+                    self.light_moved |= moved;
+                }
+            }
+            self.running = None;
+        }
+    }
+
+    /// How long a side may think about its next move, with `empty_cells` cells left on the board:
+    /// an equal share of its time for each of its remaining moves, plus most of the increment,
+    /// never more than half of its time.
+    pub fn budget(&self, side: Side, empty_cells: u16) -> Duration {
+        let left = self.remaining(side);
+        let left = left - left / SAFETY_MARGIN;
+        let moves_left = cmp::max(1, (empty_cells as u32 + 1) / 2);
+        cmp::min(left / moves_left + self.increment * 4 / 5, left / 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use reversi::Side;
+    use super::*;
+
+    /// Five minutes plus three seconds per move.
+    fn five_plus_three() -> TimeControl {
+        TimeControl { base: Duration::from_secs(300), increment: Duration::from_secs(3) }
+    }
+
+    /// Plays a move in no time (or does something else), and returns the time left to the side in seconds.
+    fn play(clocks: &mut Clocks, side: Side, moved: bool) -> u64 {
+        clocks.start(side);
+        clocks.stop(moved);
+        clocks.remaining(side).as_secs()
+    }
+
+    #[test]
+    fn time_controls_are_minutes_plus_seconds() {
+        assert_eq!(TimeControl::parse("5+3"), Some(five_plus_three()));
+        assert_eq!(TimeControl::parse(" 0 + 10 "), Some(TimeControl { base: Duration::from_secs(0), increment: Duration::from_secs(10) }));
+        assert_eq!(TimeControl::parse("0+0"), None);
+        assert_eq!(TimeControl::parse("x"), None);
+        assert_eq!(TimeControl::parse("5"), None);
+        assert_eq!(TimeControl::parse("5+x"), None);
+        assert_eq!(TimeControl::parse(&five_plus_three().to_string()), Some(five_plus_three()));
+    }
+
+    #[test]
+    fn the_increment_comes_once_per_move() {
+        let mut clocks = Clocks::new(five_plus_three());
+        assert_eq!(clocks.remaining(Side::Dark), Duration::from_secs(303));
+        // The first move already had its increment, and a bit of time went by
+        assert_eq!(play(&mut clocks, Side::Dark, true), 302);
+        assert_eq!(play(&mut clocks, Side::Dark, true), 305);
+        // Undoing or asking for hints is no move
+        assert_eq!(play(&mut clocks, Side::Dark, false), 305);
+        assert_eq!(clocks.remaining(Side::Light), Duration::from_secs(303));
+        assert!(!clocks.is_flagged(Side::Dark));
+    }
+
+    #[test]
+    fn flagged_sides_get_no_more_time() {
+        let mut clocks = Clocks::new(five_plus_three());
+        play(&mut clocks, Side::Light, true);
+        clocks.light = Duration::from_secs(0);
+        assert!(clocks.is_flagged(Side::Light));
+        assert_eq!(play(&mut clocks, Side::Light, true), 0);
+        assert!(clocks.is_flagged(Side::Light));
+    }
+
+    #[test]
+    fn the_budget_is_at_most_half_of_the_time_left() {
+        let mut clocks = Clocks::new(TimeControl { base: Duration::from_secs(0), increment: Duration::from_secs(100) });
+        clocks.dark = Duration::from_secs(10);
+        // 10s less the safety margin, halved
+        assert_eq!(clocks.budget(Side::Dark, 60), Duration::from_millis(4750));
+        // Otherwise a share of the time left for each move, plus most of the increment
+        let clocks = Clocks::new(TimeControl { base: Duration::from_secs(60), increment: Duration::from_secs(0) });
+        assert_eq!(clocks.budget(Side::Light, 59), Duration::from_millis(1900));
+    }
+}
@@ -0,0 +1,220 @@
+//! An alternative interface for human players, using the terminal's raw mode: the arrow keys move a cursor
+//! across the board and Enter places a disk under it, then the flipped disks turn one ring after the other.
+//! When there is no terminal to drive (e.g. the input comes from a pipe), the line-based `interface` is used instead.
+
+use std::cmp;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use termion;
+use termion::{clear, cursor};
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use reversi::board::{Board, Coord};
+use reversi::game::PlayerAction;
+use reversi::turn::Turn;
+use ai_player::Score;
+use clock::Clocks;
+use profiles::Color;
+use interface;
+use ::{Action, OtherAction};
+
+const KEYS_INFO: &'static str =
+"\tArrows or h/j/k/l - Move the cursor
+\tEnter or space     - Place a disk
+\tu - Undo  r - Redo  w - Save
+\ti - Hint  a - Analysis
+\t? - Help  q - Quit";
+
+/// Time between two frames of the flipping animation, in milliseconds.
+const FLIP_DELAY: u64 = 80;
+
+/// What the user asked for from the board.
+enum Choice {
+    Action(Action),
+    Save,
+}
+
+/// What is shown with the board besides the keys. Drawing the board clears the screen,
+/// so what the game has to tell must be drawn with it instead of being printed beforehand.
+#[derive(Debug, Default)]
+pub struct Status {
+    /// The names of the players, shown above the board (see `interface::players_to_string`).
+    pub players: String,
+    /// The message telling the last move played, if the board did not change otherwise since.
+    pub last_move: Option<String>,
+    /// The AI's evaluation of the position, while moves are analysed.
+    pub evaluation: Option<Score>,
+    /// The best moves found by the AI, marked on the board.
+    pub hints: Vec<Coord>,
+    /// Any other message, e.g. the hints with their scores.
+    pub message: Option<String>,
+}
+
+impl Status {
+    /// Forgets everything about the previous position, keeping the names of the players.
+    pub fn clear(&mut self) {
+        self.last_move = None;
+        self.evaluation = None;
+        self.hints.clear();
+        self.message = None;
+    }
+
+    /// The lines shown under the board, each ending with a newline.
+    fn lines(&self) -> String {
+        let mut lines = String::new();
+        if let Some(ref last_move) = self.last_move {
+            lines.push_str(&format!("{}\n", last_move));
+        }
+        if let Some(score) = self.evaluation {
+            lines.push_str(&format!("{}\n", interface::evaluation_bar_to_string(score)));
+        }
+        if let Some(ref message) = self.message {
+            lines.push_str(&format!("{}\n", message));
+        }
+        lines
+    }
+}
+
+/// Returns true if both input and output are a terminal, so that the cursor interface can be used.
+pub fn is_available() -> bool {
+    termion::is_tty(&io::stdin()) && termion::is_tty(&io::stdout())
+}
+
+/// Has a human player choose a move by moving the cursor, or any other action, showing the given status
+/// and the clocks if the match is played on time. The side is shown in the color of the player's profile, if any.
+/// As with `interface::human_make_move`, only a closed input means that the player quit.
+pub fn human_make_move(turn: &Turn, color: Option<Color>, status: &Mutex<Status>, clocks: Option<&Mutex<Clocks>>) -> io::Result<Action> {
+    if !is_available() {
+        return interface::human_make_move(turn, color);
+    }
+
+    // Start from a legal move, so that Enter alone plays something
+    let mut cursor = turn.get_legal_moves().first().cloned().unwrap_or(Coord::new(0, 0));
+    loop {
+        match try!(choose(turn, &mut cursor, color, status, clocks)) {
+            Some(Choice::Action(action)) => return Ok(action),
+            Some(Choice::Save) => {
+                // Back to the line interface to type the file name
+                match interface::input_file_name() {
+                    Ok(ref path) if path.is_empty() => {}
+                    Ok(path) => return Ok(PlayerAction::Other(OtherAction::Save(path))),
+                    Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(PlayerAction::Other(OtherAction::Quit)),
+                    Err(err) => return Err(err),
+                }
+            }
+            // The terminal cannot go into raw mode after all
+            None => return interface::human_make_move(turn, color),
+        }
+    }
+}
+
+/// Reads keys in raw mode until the user chooses something, or returns `None` if raw mode is not available.
+fn choose(turn: &Turn, cursor: &mut Coord, color: Option<Color>, status: &Mutex<Status>, clocks: Option<&Mutex<Clocks>>) -> io::Result<Option<Choice>> {
+    let stdout = io::stdout();
+    let mut stdout = match stdout.lock().into_raw_mode() {
+        Ok(stdout) => stdout,
+        Err(_) => return Ok(None),
+    };
+    let stdin = io::stdin();
+    let mut keys = stdin.lock().keys();
+    let size = turn.get_size() as isize;
+    let mut message = "";
+
+    loop {
+        let side = turn.get_state().expect("Moves are chosen in running turns");
+        let screen = {
+            let status = status.lock().expect("Could not read the status");
+            format!("{}{}{}{}{}{}\t{} moves: {}\n{}\n\n\t{}",
+                clear::All, cursor::Goto(1, 1), status.players, interface::board_to_string(turn, turn.get_board(), Some(*cursor), &status.hints),
+                clocks_to_string(turn, clocks), status.lines(), interface::side_label(side, color), cursor, KEYS_INFO, message)
+        };
+        // Raw mode does not go back to the start of the line by itself
+        write!(stdout, "{}", screen.replace('\n', "\r\n")).expect("Failed to draw the board!");
+        stdout.flush().expect("Failed to draw the board!");
+        message = "";
+
+        let key = match keys.next() {
+            Some(Ok(key)) => key,
+            Some(Err(err)) => return Err(err),
+            // Nothing more to read: there is nobody to play anymore
+            None => return Ok(Some(Choice::Action(PlayerAction::Other(OtherAction::Quit)))),
+        };
+        let (row, col) = (cursor.get_row() as isize, cursor.get_col() as isize);
+        let (row, col) = match key {
+            Key::Up | Key::Char('k') => (row - 1, col),
+            Key::Down | Key::Char('j') => (row + 1, col),
+            Key::Left | Key::Char('h') => (row, col - 1),
+            Key::Right | Key::Char('l') => (row, col + 1),
+            Key::Char('\n') | Key::Char(' ') => {
+                if turn.check_move(*cursor).is_ok() {
+                    return Ok(Some(Choice::Action(PlayerAction::Move(*cursor))));
+                }
+                message = "Illegal move, try again.";
+                (row, col)
+            }
+            Key::Char('u') => return Ok(Some(Choice::Action(PlayerAction::Undo))),
+            Key::Char('r') => return Ok(Some(Choice::Action(PlayerAction::Redo))),
+            Key::Char('w') => return Ok(Some(Choice::Save)),
+            Key::Char('i') => return Ok(Some(Choice::Action(PlayerAction::Other(OtherAction::Hint)))),
+            Key::Char('a') => return Ok(Some(Choice::Action(PlayerAction::Other(OtherAction::Analysis)))),
+            Key::Char('?') => return Ok(Some(Choice::Action(PlayerAction::Other(OtherAction::Help)))),
+            Key::Char('q') | Key::Ctrl('c') => return Ok(Some(Choice::Action(PlayerAction::Other(OtherAction::Quit)))),
+            _ => (row, col),
+        };
+        // The cursor stops at the edges of the board
+        *cursor = Coord::new(cmp::max(0, cmp::min(row, size - 1)) as usize, cmp::max(0, cmp::min(col, size - 1)) as usize);
+    }
+}
+
+/// Draws a move being played: the new disk is placed, then the disks it flips turn over ring by ring,
+/// from the closest to the farthest. The names of the players are shown above the board, and the clocks, if any, under it.
+pub fn animate_move(players: &str, before: &Turn, coord: Coord, after: &Turn, clocks: Option<&Mutex<Clocks>>) {
+    let mut board = before.get_board().clone();
+    let side = before.get_state().expect("Moves are played in running turns");
+    board.place_disk(side, coord).expect("The move was legal");
+
+    let mut flipped: Vec<Coord> = Vec::new();
+    for row in 0..board.get_size() {
+        for col in 0..board.get_size() {
+            let cell = Coord::new(row, col);
+            let was = before.get_cell(cell).expect("The cell is on the board").map(|disk| disk.get_side());
+            let is = after.get_cell(cell).expect("The cell is on the board").map(|disk| disk.get_side());
+            if cell != coord && was.is_some() && was != is {
+                flipped.push(cell);
+            }
+        }
+    }
+    let distance = |cell: &Coord| {
+        let rows = (cell.get_row() as isize - coord.get_row() as isize).abs();
+        let cols = (cell.get_col() as isize - coord.get_col() as isize).abs();
+        cmp::max(rows, cols)
+    };
+    flipped.sort_by_key(&distance);
+
+    draw_frame(players, after, &board, clocks);
+    let mut flipped = flipped.into_iter().peekable();
+    while let Some(cell) = flipped.next() {
+        board.flip_disk(cell).expect("There is a disk to flip");
+        // Show each ring once all of its disks are flipped
+        if flipped.peek().map(&distance) != Some(distance(&cell)) {
+            thread::sleep(Duration::from_millis(FLIP_DELAY));
+            draw_frame(players, after, &board, clocks);
+        }
+    }
+}
+
+fn draw_frame(players: &str, turn: &Turn, board: &Board, clocks: Option<&Mutex<Clocks>>) {
+    print!("{}{}{}{}{}", clear::All, cursor::Goto(1, 1), players, interface::board_to_string(turn, board, None, &[]), clocks_to_string(turn, clocks));
+    let _ = io::stdout().flush();
+}
+
+/// The time left to each side, to be drawn under the board, if the match is played on time.
+fn clocks_to_string(turn: &Turn, clocks: Option<&Mutex<Clocks>>) -> String {
+    match clocks {
+        Some(clocks) => interface::clocks_to_string(turn.get_size(), &clocks.lock().expect("Could not read the clocks")),
+        None => String::new(),
+    }
+}
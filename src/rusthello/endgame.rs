@@ -0,0 +1,157 @@
+//! Exact endgame solver.
+//! When few empty cells remain, the whole game tree can be searched: the solver then returns the provably optimal move
+//! together with the final disk differential that perfect play from both sides leads to.
+
+use reversi;
+use reversi::bitboard::{self, Bits};
+use reversi::board::Coord;
+use reversi::turn::Turn;
+use ::Result;
+
+/// Below this number of empty cells, move ordering is not worth its cost anymore.
+const ORDERING_EMPTIES: u32 = 7;
+
+/// The outcome of an exact search.
+#[derive(Debug, Clone, Copy)]
+pub struct Solution {
+    /// The optimal move.
+    pub coord: Coord,
+    /// The final disk differential (own disks minus opponent's disks) for the player to move, if both play perfectly.
+    pub differential: i16,
+}
+
+/// An exact solver, activating only when there are at most `max_empties` empty cells.
+pub struct Solver {
+    max_empties: u16,
+    /// The cells of the board being solved.
+    cells: Bits,
+}
+
+impl Solver {
+    /// Creates a solver which only tries to solve positions with at most `max_empties` empty cells.
+    pub fn new(max_empties: u16) -> Solver {
+        Solver {
+            max_empties: max_empties,
+            cells: Bits::empty(),
+        }
+    }
+
+    /// Solves the given turn, or returns `None` if it has too many empty cells.
+    pub fn solve(&mut self, turn: &Turn) -> Result<Option<Solution>> {
+        let side = try!(turn.get_state().ok_or(reversi::ReversiError::EndedGame));
+        if turn.get_empty_cells() > self.max_empties {
+            return Ok(None);
+        }
+
+        let own = turn.get_board().get_bits(side);
+        let opp = turn.get_board().get_bits(side.opposite());
+        self.cells = turn.get_board().get_cells_bits();
+
+        // Lower than any differential
+        let worst = -(self.cells.count_ones() as i16) - 1;
+        let mut alpha = worst;
+        let mut best = None;
+        for mv in self.ordered_moves(own, opp) {
+            let flipped = bitboard::flips(own, opp, mv);
+            let value = -self.negamax(opp & !flipped, own | flipped | mv, worst, -alpha);
+            if value > alpha {
+                alpha = value;
+                best = Some(mv);
+            }
+        }
+
+        Ok(best.map(|mv| Solution {
+            coord: bitboard::to_coord(mv.first().expect("Moves are single cells")),
+            differential: alpha,
+        }))
+    }
+
+    /// Exact value (final disk differential) of a position for the player owning `own`, who is to move.
+    fn negamax(&mut self, own: Bits, opp: Bits, mut alpha: i16, beta: i16) -> i16 {
+        let moves = bitboard::legal_moves(own, opp, self.cells);
+        if moves.is_empty() {
+            return if bitboard::legal_moves(opp, own, self.cells).is_empty() {
+                // Neither player can move: the game is ended
+                own.count_ones() as i16 - opp.count_ones() as i16
+            } else {
+                // Pass
+                -self.negamax(opp, own, -beta, -alpha)
+            };
+        }
+
+        let mut best = -(self.cells.count_ones() as i16) - 1;
+        for mv in self.ordered_moves(own, opp) {
+            let flipped = bitboard::flips(own, opp, mv);
+            let value = -self.negamax(opp & !flipped, own | flipped | mv, -beta, -alpha);
+            if value > best {
+                best = value;
+                if value > alpha {
+                    alpha = value;
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// Lists the legal moves (each as a single-cell set), those leaving the opponent fewer replies first.
+    fn ordered_moves(&self, own: Bits, opp: Bits) -> Vec<Bits> {
+        let mut moves: Vec<Bits> = bitboard::legal_moves(own, opp, self.cells).indexes()
+            .into_iter().map(Bits::single).collect();
+
+        if (self.cells & !(own | opp)).count_ones() > ORDERING_EMPTIES {
+            moves.sort_by_key(|&mv| {
+                let flipped = bitboard::flips(own, opp, mv);
+                bitboard::legal_moves(opp & !flipped, own | flipped | mv, self.cells).count_ones()
+            });
+        }
+        moves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{ChaChaRng, Rng, SeedableRng};
+    use reversi::Side;
+    use reversi::turn::Turn;
+    use super::Solver;
+
+    /// Plain minimax over `Turn`s: the final differential for `side`.
+    fn minimax(turn: &Turn, side: Side) -> i16 {
+        match turn.get_state() {
+            None => match side {
+                Side::Light => turn.get_score_diff(),
+                Side::Dark => -turn.get_score_diff(),
+            },
+            Some(to_move) => {
+                let values = turn.get_legal_moves().into_iter().map(|coord| minimax(&turn.make_move(coord).unwrap(), side));
+                if to_move == side {
+                    values.max().unwrap()
+                } else {
+                    values.min().unwrap()
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn solver_agrees_with_minimax() {
+        for seed in 0..20 {
+            let mut rng = ChaChaRng::from_seed(&[seed]);
+            let mut turn = Turn::first_turn();
+            while turn.get_empty_cells() > 8 && !turn.is_endgame() {
+                let moves = turn.get_legal_moves();
+                turn = turn.make_move(moves[rng.gen_range(0, moves.len())]).unwrap();
+            }
+
+            if let Some(side) = turn.get_state() {
+                let solution = Solver::new(8).solve(&turn).unwrap().expect("Few enough empty cells");
+                assert_eq!(solution.differential, minimax(&turn, side));
+                let after = turn.make_move(solution.coord).unwrap();
+                assert_eq!(minimax(&after, side), solution.differential);
+            }
+        }
+    }
+}
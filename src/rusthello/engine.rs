@@ -0,0 +1,355 @@
+//! Engine mode: a line-based text protocol on stdin/stdout, modelled on the Go Text Protocol (GTP),
+//! so that external GUIs and tournament managers can drive the AI without the interactive menus.
+//!
+//! Each command is a line `[id] name [arguments]`. The engine answers `=[id] result` on success
+//! and `?[id] message` on failure, each answer ending with an empty line. Text after `#` is ignored.
+//! Colors are `b`/`black`/`dark` and `w`/`white`/`light`, moves are cell names such as `f5` or `pass`,
+//! and positions are written as in `Turn::from_position`.
+
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+use std::time::Duration;
+use reversi::{self, Side};
+use reversi::game::{IsPlayer, PlayerAction};
+use reversi::turn::Turn;
+use ai_player::{AiPlayer, Strength};
+use evaluation::Weights;
+use book::Book;
+
+/// Version of the protocol, as in GTP.
+const PROTOCOL_VERSION: &'static str = "2";
+
+/// Thinking time per move, unless set otherwise.
+const DEFAULT_TIME: u64 = 1000;
+
+const COMMANDS: [&'static str; 15] = [
+    "protocol_version",
+    "name",
+    "version",
+    "known_command",
+    "list_commands",
+    "quit",
+    "boardsize",
+    "clear_board",
+    "set_position",
+    "play",
+    "genmove",
+    "undo",
+    "set_strength",
+    "showboard",
+    "final_score",
+];
+
+/// The state of the engine: the game so far and how the AI plays.
+struct Engine {
+    /// All turns of the game, from the first one to the current one.
+    turns: Vec<Turn>,
+    strength: Strength,
+    /// The AI, kept from move to move so that its workers are started once.
+    player: AiPlayer,
+    seed: u32,
+    /// Whether `quit` was received.
+    quitting: bool,
+}
+
+/// Runs the engine on stdin and stdout until `quit` or the end of the input.
+pub fn run(weights: Weights, book: Arc<Book>, seed: u32) -> io::Result<()> {
+    let mut engine = Engine::new(weights, book, seed);
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for line in stdin.lock().lines() {
+        if let Some(answer) = engine.respond(&try!(line)) {
+            try!(stdout.write_all(answer.as_bytes()));
+            try!(stdout.flush());
+        }
+        if engine.quitting {
+            break;
+        }
+    }
+    Ok(())
+}
+
+impl Engine {
+    fn new(weights: Weights, book: Arc<Book>, seed: u32) -> Engine {
+        Engine {
+            turns: vec![Turn::first_turn()],
+            strength: Strength::Timed(Duration::from_millis(DEFAULT_TIME)),
+            player: AiPlayer::new(Strength::Timed(Duration::from_millis(DEFAULT_TIME)), seed).with_weights(weights).with_book(book),
+            seed: seed,
+            quitting: false,
+        }
+    }
+
+    /// Answers a line of input, unless it holds no command.
+    fn respond(&mut self, line: &str) -> Option<String> {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut words = line.split_whitespace().peekable();
+
+        // An optional numeric id comes first, and is repeated in the answer
+        let id = match words.peek().and_then(|word| word.parse::<u32>().ok()) {
+            Some(id) => {
+                words.next();
+                id.to_string()
+            }
+            None => String::new(),
+        };
+        let name = match words.next() {
+            Some(name) => name.to_lowercase(),
+            None => return None,
+        };
+        let args: Vec<&str> = words.collect();
+
+        Some(match self.execute(&name, &args) {
+            Ok(result) => format!("={} {}\n\n", id, result),
+            Err(msg) => format!("?{} {}\n\n", id, msg),
+        })
+    }
+
+    fn current_turn(&self) -> &Turn {
+        self.turns.last().expect("There is a first turn")
+    }
+
+    /// Runs a command and returns the text of the answer.
+    fn execute(&mut self, name: &str, args: &[&str]) -> Result<String, String> {
+        match (name, args.len()) {
+            ("protocol_version", 0) => Ok(PROTOCOL_VERSION.to_string()),
+            ("name", 0) => Ok("RUSThello".to_string()),
+            ("version", 0) => Ok(env!("CARGO_PKG_VERSION").to_string()),
+            ("known_command", 1) => Ok(COMMANDS.contains(&args[0]).to_string()),
+            ("list_commands", 0) => Ok(COMMANDS.join("\n")),
+            ("quit", 0) => {
+                self.quitting = true;
+                Ok(String::new())
+            }
+            ("boardsize", 1) => {
+                let size = try!(args[0].parse().map_err(|_| "invalid board size".to_string()));
+                self.new_game(try!(Turn::new_game(size).map_err(to_message)))
+            }
+            ("clear_board", 0) => {
+                let size = self.current_turn().get_size();
+                self.new_game(try!(Turn::new_game(size).map_err(to_message)))
+            }
+            ("set_position", _) if !args.is_empty() => {
+                self.new_game(try!(Turn::from_position(&args.join(" ")).map_err(to_message)))
+            }
+            ("play", 2) => {
+                let side = try!(parse_side(args[0]));
+                self.play(side, args[1])
+            }
+            ("genmove", 1) | ("genmove", 2) => {
+                let side = try!(parse_side(args[0]));
+                let strength = match args.get(1) {
+                    Some(secs) => Strength::Timed(try!(parse_time(secs))),
+                    None => self.strength,
+                };
+                self.genmove(side, strength)
+            }
+            ("undo", 0) => {
+                if self.turns.len() > 1 {
+                    self.turns.pop();
+                    Ok(String::new())
+                } else {
+                    Err("cannot undo".to_string())
+                }
+            }
+            ("set_strength", 1) => {
+                self.strength = match &*args[0].to_lowercase() {
+                    "weak" => Strength::Weak,
+                    "medium" => Strength::Medium,
+                    "strong" => Strength::Strong,
+                    secs => Strength::Timed(try!(parse_time(secs))),
+                };
+                Ok(String::new())
+            }
+            ("showboard", 0) => Ok(show_board(self.current_turn())),
+            ("final_score", 0) => {
+                // From Dark's point of view, as in GGF results
+                match -self.current_turn().get_score_diff() {
+                    0 => Ok("0".to_string()),
+                    diff if diff > 0 => Ok(format!("B+{}", diff)),
+                    diff => Ok(format!("W+{}", -diff)),
+                }
+            }
+            _ if COMMANDS.contains(&name) => Err("wrong number of arguments".to_string()),
+            _ => Err("unknown command".to_string()),
+        }
+    }
+
+    fn new_game(&mut self, first_turn: Turn) -> Result<String, String> {
+        self.turns = vec![first_turn];
+        Ok(String::new())
+    }
+
+    /// Plays a move for a side. Passing is only allowed to a side which cannot move, and changes nothing
+    /// since the turn already went to the other side.
+    fn play(&mut self, side: Side, mv: &str) -> Result<String, String> {
+        let to_move = self.current_turn().get_state();
+        if mv.eq_ignore_ascii_case("pass") {
+            return if to_move == Some(side) { Err("illegal move".to_string()) } else { Ok(String::new()) };
+        }
+        if to_move != Some(side) {
+            return Err("illegal move: not this side's turn".to_string());
+        }
+        let coord = try!(mv.parse().map_err(|_| "invalid coordinates".to_string()));
+        let next = try!(self.current_turn().make_move(coord).map_err(|_| "illegal move".to_string()));
+        self.turns.push(next);
+        Ok(String::new())
+    }
+
+    /// Has the AI choose a move for a side, plays it and returns it.
+    fn genmove(&mut self, side: Side, strength: Strength) -> Result<String, String> {
+        if self.current_turn().get_state() != Some(side) {
+            return Ok("pass".to_string());
+        }
+        // A different seed for every move, but the same game from the same commands
+        let seed = self.seed.wrapping_add(self.turns.len() as u32);
+        self.player.reseed(seed);
+        self.player.set_strength(strength);
+        let coord = match try!(self.player.make_move(self.current_turn()).map_err(to_message)) {
+            PlayerAction::Move(coord) => coord,
+            _ => return Err("no move".to_string()),
+        };
+        let next = try!(self.current_turn().make_move(coord).map_err(to_message));
+        self.turns.push(next);
+        Ok(coord.to_string())
+    }
+}
+
+fn parse_side(color: &str) -> Result<Side, String> {
+    match &*color.to_lowercase() {
+        "b" | "black" | "dark" => Ok(Side::Dark),
+        "w" | "white" | "light" => Ok(Side::Light),
+        _ => Err("invalid color".to_string()),
+    }
+}
+
+/// Parses a thinking time in seconds, possibly with decimals.
+fn parse_time(secs: &str) -> Result<Duration, String> {
+    match secs.parse::<f64>() {
+        Ok(secs) if secs > 0f64 => Ok(Duration::from_millis((secs * 1000f64) as u64)),
+        _ => Err("invalid time".to_string()),
+    }
+}
+
+/// Draws the board as text: Dark's disks are `*`, Light's disks are `O`, empty cells are `-`.
+fn show_board(turn: &Turn) -> String {
+    let size = turn.get_size();
+    let letters: Vec<String> = (0..size).map(|col| ((b'A' + col as u8) as char).to_string()).collect();
+    let column_reference = format!("   {}", letters.join(" "));
+
+    let mut board = format!("\n{}\n", column_reference);
+    for (row, cells) in turn.get_board().get_all_cells().iter().enumerate() {
+        board.push_str(&format!("{:>2}", row + 1));
+        for cell in cells {
+            board.push(' ');
+            board.push(match *cell {
+                Some(disk) if disk.get_side() == Side::Dark => '*',
+                Some(_) => 'O',
+                None => '-',
+            });
+        }
+        board.push_str(&format!(" {}\n", row + 1));
+    }
+    board.push_str(&column_reference);
+
+    let (score_dark, score_light) = turn.get_score();
+    board.push_str(&format!("\nDark (*) {} - {} Light (O), ", score_dark, score_light));
+    board.push_str(match turn.get_state() {
+        Some(Side::Dark) => "Dark to move",
+        Some(Side::Light) => "Light to move",
+        None => "game over",
+    });
+    board
+}
+
+fn to_message(err: reversi::ReversiError) -> String {
+    err.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use evaluation::Weights;
+    use book::Book;
+    use super::Engine;
+
+    fn engine() -> Engine {
+        Engine::new(Weights::default(), Arc::new(Book::default()), 0)
+    }
+
+    /// Runs the commands in order, and returns the result of the last one.
+    fn run(engine: &mut Engine, commands: &[&str]) -> Result<String, String> {
+        let mut result = Ok(String::new());
+        for command in commands {
+            let words: Vec<&str> = command.split_whitespace().collect();
+            result = engine.execute(words[0], &words[1..]);
+        }
+        result
+    }
+
+    #[test]
+    fn moves_are_played_by_the_side_to_move() {
+        let mut engine = engine();
+        assert!(run(&mut engine, &["play w f5"]).is_err());
+        assert!(run(&mut engine, &["play b f4"]).is_err());
+        assert_eq!(run(&mut engine, &["play b f5", "play w d6"]), Ok(String::new()));
+        assert_eq!(engine.turns.len(), 3);
+    }
+
+    #[test]
+    fn only_a_side_which_cannot_move_passes() {
+        let mut engine = engine();
+        assert!(run(&mut engine, &["play b pass"]).is_err());
+        // After c1, Light cannot move
+        assert!(run(&mut engine, &["set_position 4 *O-- ---- O--- *--- *", "play b c1", "play w pass"]).is_ok());
+        assert_eq!(engine.turns.len(), 2);
+        assert!(run(&mut engine, &["play b pass"]).is_err());
+    }
+
+    #[test]
+    fn the_first_turn_cannot_be_undone() {
+        let mut engine = engine();
+        assert!(run(&mut engine, &["undo"]).is_err());
+        assert!(run(&mut engine, &["play b f5", "undo"]).is_ok());
+        assert!(run(&mut engine, &["undo"]).is_err());
+    }
+
+    #[test]
+    fn board_sizes_are_checked() {
+        let mut engine = engine();
+        assert!(run(&mut engine, &["boardsize 3"]).is_err());
+        assert!(run(&mut engine, &["boardsize 17"]).is_err());
+        assert!(run(&mut engine, &["boardsize x"]).is_err());
+        assert!(run(&mut engine, &["boardsize 6"]).is_ok());
+        assert_eq!(engine.current_turn().get_size(), 6);
+    }
+
+    #[test]
+    fn final_scores_are_from_darks_point_of_view() {
+        let mut engine = engine();
+        assert_eq!(run(&mut engine, &["final_score"]), Ok("0".to_string()));
+        assert_eq!(run(&mut engine, &["set_position 4 *O-- ---- O--- *--- *", "play b c1", "play b a2", "final_score"]), Ok("B+6".to_string()));
+        assert_eq!(run(&mut engine, &["set_position 4 O*-- ---- *--- O--- O", "play w c1", "play w a2", "final_score"]), Ok("W+6".to_string()));
+    }
+
+    #[test]
+    fn known_commands_are_told() {
+        let mut engine = engine();
+        assert_eq!(run(&mut engine, &["known_command genmove"]), Ok("true".to_string()));
+        assert_eq!(run(&mut engine, &["known_command fly"]), Ok("false".to_string()));
+    }
+
+    #[test]
+    fn answers_repeat_the_id() {
+        let mut engine = engine();
+        assert_eq!(engine.respond("7 name"), Some("=7 RUSThello\n\n".to_string()));
+        assert_eq!(engine.respond("fly away # comment"), Some("? unknown command\n\n".to_string()));
+        assert_eq!(engine.respond("3 play b"), Some("?3 wrong number of arguments\n\n".to_string()));
+        assert_eq!(engine.respond("  # nothing"), None);
+        assert!(!engine.quitting);
+        assert_eq!(engine.respond("quit"), Some("= \n\n".to_string()));
+        assert!(engine.quitting);
+    }
+}
@@ -0,0 +1,161 @@
+//! Pattern-based evaluation of positions, with weights which can be tuned and stored in a text file.
+//!
+//! Every feature compares the two players and lies between -1 (all in favour of Dark) and 1 (all in favour of Light).
+//! The evaluation is the average of the features, weighted by the absolute value of their weights,
+//! so it lies between -1 and 1 as well.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use reversi;
+use reversi::bitboard::{self, Bits};
+use reversi::board::{Direction, DIRECTIONS};
+use reversi::turn::Turn;
+use ai_player::AiPlayer;
+use ::Result;
+
+/// The file the weights are read from and the tuner writes them to.
+pub const WEIGHTS_FILE: &'static str = "rusthello_weights.txt";
+
+/// Number of features of a position.
+pub const NUM_FEATURES: usize = 6;
+
+/// The names of the features, as they appear in the weights file.
+pub const FEATURES: [&'static str; NUM_FEATURES] = [
+    "corners",
+    "mobility",
+    "potential_mobility",
+    "frontier",
+    "stability",
+    "parity",
+];
+
+const DEFAULT_WEIGHTS: [f64; NUM_FEATURES] = [1.0, 0.5, 0.2, 0.2, 0.5, 0.1];
+
+/// Pairs of opposite directions: a disk is stable along an axis if it is protected on one of its sides.
+const AXES: [(Direction, Direction); 4] = [
+    (Direction::North, Direction::South),
+    (Direction::East, Direction::West),
+    (Direction::NE, Direction::SW),
+    (Direction::NW, Direction::SE),
+];
+
+/// The weights of the features, in the order of `FEATURES`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+    pub values: [f64; NUM_FEATURES],
+}
+
+impl Default for Weights {
+    fn default() -> Weights {
+        Weights {
+            values: DEFAULT_WEIGHTS,
+        }
+    }
+}
+
+impl Weights {
+    /// Reads weights from a file made of lines `name value`. Empty lines and lines starting with `#` are ignored,
+    /// and features missing from the file keep their default weight.
+    pub fn load(path: &str) -> io::Result<Weights> {
+        let mut text = String::new();
+        try!(File::open(path).and_then(|mut file| file.read_to_string(&mut text)));
+
+        let mut weights = Weights::default();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let mut words = line.split_whitespace();
+            let name = words.next().unwrap_or("");
+            let index = try!(FEATURES.iter().position(|&feature| feature == name)
+                .ok_or_else(|| invalid(format!("unknown feature '{}'", name))));
+            weights.values[index] = try!(words.next().and_then(|value| value.parse().ok())
+                .ok_or_else(|| invalid(format!("invalid weight for '{}'", name))));
+        }
+        Ok(weights)
+    }
+
+    /// Writes the weights to a file, in the format read by `load`.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut file = try!(File::create(path));
+        try!(writeln!(file, "# RUSThello evaluation weights"));
+        for (name, value) in FEATURES.iter().zip(self.values.iter()) {
+            try!(writeln!(file, "{} {:.3}", name, value));
+        }
+        Ok(())
+    }
+
+    /// Evaluates a position: a value between -1 (good for Dark) and 1 (good for Light).
+    pub fn evaluate(&self, turn: &Turn) -> Result<f64> {
+        let total: f64 = self.values.iter().map(|weight| weight.abs()).sum();
+        if total == 0f64 {
+            return Ok(0f64);
+        }
+        let features = try!(features(turn));
+        Ok(features.iter().zip(self.values.iter()).map(|(feature, weight)| feature * weight).sum::<f64>() / total)
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Computes all features of a position, in the order of `FEATURES`.
+pub fn features(turn: &Turn) -> Result<[f64; NUM_FEATURES]> {
+    let board = turn.get_board();
+    let light = board.get_bits(reversi::Side::Light);
+    let dark = board.get_bits(reversi::Side::Dark);
+    let cells = board.get_cells_bits();
+    let empty = cells & !(light | dark);
+
+    Ok([
+        try!(AiPlayer::heavy_eval(turn)),
+        balance(board.legal_moves(reversi::Side::Light).count_ones(), board.legal_moves(reversi::Side::Dark).count_ones()),
+        // Empty cells next to the opponent's disks are where moves may become available
+        balance((neighbours(dark) & empty).count_ones(), (neighbours(light) & empty).count_ones()),
+        // Disks next to empty cells are easily flipped: having fewer of them is better
+        balance((dark & neighbours(empty)).count_ones(), (light & neighbours(empty)).count_ones()),
+        balance(stable(light, cells).count_ones(), stable(dark, cells).count_ones()),
+        parity(turn),
+    ])
+}
+
+/// Compares two counts: 1 if all is on Light's side, -1 if all is on Dark's side.
+fn balance(light: u32, dark: u32) -> f64 {
+    if light + dark == 0 {
+        0f64
+    } else {
+        (light as f64 - dark as f64) / (light + dark) as f64
+    }
+}
+
+/// The cells next to some cell of a set.
+fn neighbours(bits: Bits) -> Bits {
+    DIRECTIONS.iter().fold(Bits::empty(), |acc, &dir| acc | bitboard::shift(bits, dir))
+}
+
+/// A conservative estimate of the disks of a player which can never be flipped:
+/// those which, along each axis, lie on the border of the board (made of the cells in `cells`)
+/// or next to another stable disk.
+fn stable(own: Bits, cells: Bits) -> Bits {
+    let mut stable = Bits::empty();
+    loop {
+        let mut next = own;
+        for &(dir, opposite) in &AXES {
+            next &= bitboard::shift(stable, dir) | bitboard::shift(stable, opposite)
+                | !bitboard::shift(cells, dir) | !bitboard::shift(cells, opposite);
+        }
+        if next == stable {
+            return stable;
+        }
+        stable = next;
+    }
+}
+
+/// Whoever is to move with an odd number of empty cells left is expected to play the last move.
+fn parity(turn: &Turn) -> f64 {
+    let odd = turn.get_empty_cells() % 2 == 1;
+    match turn.get_state() {
+        Some(reversi::Side::Light) if odd => 1f64,
+        Some(reversi::Side::Dark) if !odd => 1f64,
+        Some(_) => -1f64,
+        None => 0f64,
+    }
+}
@@ -1,15 +1,58 @@
 //! Provides `game::IsPlayer<::OtherAction>` types.
 
+use std::sync::{Arc, Mutex};
 use interface;
+use cursor_interface::{self, Status};
+use clock::Clocks;
+use profiles::Color;
 use reversi::{turn, game};
 use ::{Action, Result};
 
 /// The type of human players. Its 'make_move' calls the interface to ask user for an input.
-pub struct HumanPlayer;
+pub struct HumanPlayer {
+    /// The color of the player's profile, if any.
+    color: Option<Color>,
+}
+
+impl HumanPlayer {
+    pub fn new(color: Option<Color>) -> HumanPlayer {
+        HumanPlayer {
+            color: color,
+        }
+    }
+}
 
 impl game::IsPlayer<::OtherAction> for HumanPlayer {
     /// Calls the interface to ask user for an input.
     fn make_move(&self, turn: &turn::Turn) -> Result<Action> {
-        interface::human_make_move(turn)
+        Ok(interface::human_make_move(turn, self.color).unwrap_or_else(|err| game::PlayerAction::Other(::OtherAction::InputError(err))))
+    }
+}
+
+/// The type of human players using the cursor interface, where possible.
+pub struct CursorPlayer {
+    /// The color of the player's profile, if any.
+    color: Option<Color>,
+    /// What is shown under the board, as told by the game.
+    status: Arc<Mutex<Status>>,
+    /// The clocks of the match, if it is played on time.
+    clocks: Option<Arc<Mutex<Clocks>>>,
+}
+
+impl CursorPlayer {
+    pub fn new(color: Option<Color>, status: Arc<Mutex<Status>>, clocks: Option<Arc<Mutex<Clocks>>>) -> CursorPlayer {
+        CursorPlayer {
+            color: color,
+            status: status,
+            clocks: clocks,
+        }
+    }
+}
+
+impl game::IsPlayer<::OtherAction> for CursorPlayer {
+    /// Lets the user move the cursor to the cell to play.
+    fn make_move(&self, turn: &turn::Turn) -> Result<Action> {
+        let action = cursor_interface::human_make_move(turn, self.color, &self.status, self.clocks.as_ref().map(|clocks| &**clocks));
+        Ok(action.unwrap_or_else(|err| game::PlayerAction::Other(::OtherAction::InputError(err))))
     }
 }
@@ -2,11 +2,17 @@
 
 use std::string::String;
 use std::io::{self, Write};
-use reversi::Side;
-use reversi::board::{BOARD_SIZE, Coord};
+use reversi::{ReversiError, Side};
+use reversi::board::{DEFAULT_BOARD_SIZE, Board, Coord};
 use reversi::game::PlayerAction;
 use reversi::turn::{State, Turn};
-use ::{Result, Action, OtherAction};
+use ::{Action, OtherAction};
+use endgame::Solution;
+use ai_player::Score;
+use search::ENDGAME_SCALE;
+use clock::{Clocks, TimeControl};
+use profiles::{self, Color, Results};
+use std::time::Duration;
 use termion::{color, style};
 
 // ANSI version
@@ -15,14 +21,54 @@ const LIGHT_DISK: char = '@';
 const EMPTY_CELL: char = '*';
 const LEGAL_MOVE: char = '*';
 
+/// How many of the best moves are shown by hints.
+const HINT_MOVES: usize = 3;
+
+/// Width of the evaluation bar, in characters.
+const BAR_WIDTH: usize = 25;
+
+/// The commands of the main menu.
 pub enum UserCommand {
-    NewGame,
-    HumanPlayer,
+    /// Start a new match on a board of the given size, with clocks if a time control is given.
+    NewGame(usize, Option<TimeControl>),
+    /// Start a new match from the position in the given file.
+    NewGameFrom(String),
+    /// Join the match hosted at the given address.
+    Join(String),
+    /// Load a saved game from the given file.
+    Load(String),
+    /// Save the last game to the given file.
+    Save(String),
+    /// Replay a saved game from the given file.
+    Replay(String),
+    Help,
+    Credits,
+    Quit,
+}
+
+/// The players which can be chosen for a side.
+pub enum PlayerChoice {
+    /// A human player, with the name and possibly the color of their profile.
+    HumanPlayer(Option<(String, Option<Color>)>),
+    /// A human player using the cursor interface.
+    HumanCursor(Option<(String, Option<Color>)>),
     AiWeak,
     AiMedium,
     AiStrong,
-    Help,
-    Credits,
+    AiTimed(u64),
+    /// A remote player, whose peer is waited for on the given port.
+    Remote(u16),
+    /// Nobody: the match is not played after all.
+    Quit,
+}
+
+/// The commands of a replay.
+pub enum ReplayCommand {
+    Forward,
+    Back,
+    First,
+    Last,
+    Analysis,
     Quit,
 }
 
@@ -65,7 +111,17 @@ pub fn intro() {
 }
 
 const MAIN_MENU: &'static str =
-"\tn - New match
+"\tn [SIZE] [M+S] - New match
+\t   (board side 4 to 16, with
+\t   M minutes per side plus
+\t   S seconds per move)
+\tp FILE - New match from
+\t         a custom position
+\tj HOST:PORT - Join a match
+\t         hosted remotely
+\tl FILE - Load a match
+\ts FILE - Save last match
+\tr FILE - Replay a match
 \th - Help
 \tc - Credits
 \tq - Quit RUSThello";
@@ -78,9 +134,18 @@ pub fn main_menu() {
 
 const NEW_PLAYER_MENU: &'static str =
 "\th - Human Player
+\tk - Human Player
+\t      with cursor keys
+\th NAME [COLOR] - Human
+\t      Player with a profile
+\t      keeping statistics
 \tw - Weak   AI
 \tm - Medium AI
 \ts - Strong AI
+\tt N - AI thinking
+\t      N seconds per move
+\tr PORT - Remote player
+\t      joining on PORT
 \tq - Quit match";
 
 pub fn new_player_menu() {
@@ -95,6 +160,10 @@ const COMMANDS_INFO: &'static str = "\n\n
 \tExaple: \"c4\" (or \"C4\", \"4c\", \"4C\", etc...).
 \tType 'help' or 'h' to display a help message.
 \tType 'undo' or 'u' to undo the last move.
+\tType 'redo' or 'r' to redo what was undone.
+\tType 'hint' to see the best moves according to the AI.
+\tType 'analysis' or 'a' to see who is ahead after every move (or to stop it).
+\tType 'save FILE' to save the game (in GGF if FILE ends with '.ggf').
 \tType 'quit' or 'q' to abandon the game.";
 
 pub fn commands_info() {
@@ -135,19 +204,63 @@ of 'c4', 'C4', '4c' and '4C' are valid and equivalent coordinates. For your ease
 legal moves are marked on the board by an asterisk.\n
 \tFurthermore, on your turn you can \
 also input special commands: 'undo' (or 'u') to undo your last move (and yes, you can 'undo' \
-as many times as you like), 'help' (or 'h') to see this help message again, and 'quit' (or \
-'q') to quit the game.";
-
-pub fn help() {
+as many times as you like), 'redo' (or 'r') to play again the moves you undid (as long as no \
+other move was made in the meantime), 'hint' to have the AI mark its favourite moves on the board (and tell \
+the perfect move once few enough cells are left empty), 'analysis' (or 'a') to have a bar show who is ahead \
+after every move, 'save FILE' to save the game so far (as a list of moves like 'f5d6c3', or in GGF \
+if the file name ends with '.ggf'), 'help' (or 'h') to see this help message again, and 'quit' (or 'q') to quit the game.\n
+\tMatches are played on the standard 8x8 board unless you ask for another size, e.g. 'n 6' or 'n 10' \
+(any even size from 4 to 16). You can also start from a custom position with 'p FILE', where the file \
+holds the size of the board, its cells row by row ('*' for Dark, 'O' for Light, '-' for empty) and \
+the side to move, e.g. '4 -*O- -O*- ---- ---- *'. Such matches can only be saved in GGF.\n
+\tMatches can also be played on time: 'n 5+3' gives each side 5 minutes plus 3 seconds for every \
+move it makes (and 'n 6 5+3' does the same on a 6x6 board). The time left is shown under the scores, \
+and a side whose time runs out loses the match. AIs of any strength never take \
+longer than their clock allows.\n
+\tTo play someone on another computer, choose 'r PORT' for their side: RUSThello waits for them \
+on that port, and they join with 'j HOST:PORT' in the main menu (e.g. 'j 192.168.1.2:7777'), choosing \
+who plays the other side. Both ends check every move, and undoing, redoing or quitting is passed on to \
+the other end. A match on time is played with the same clocks at both ends, and a side running out of time \
+loses at both ends. Nobody joining within 5 minutes cancels the match.\n
+\tChoosing 'k' instead of 'h' for a human player lets you play with the keyboard: the arrow keys \
+(or h, j, k, l) move a cursor across the board and Enter (or space) places your disk, while 'u', 'r', \
+'w', 'i', 'a', '?' and 'q' undo, redo, save, show hints, toggle the analysis, show this help and quit. \
+The last move, the hints and the evaluation bar are shown with the board, and the flipped disks are animated. Without \
+a terminal, e.g. when the input is piped, typed commands are used instead.\n
+\tHuman players can be given a name, e.g. 'h Alice' or 'k Bob': RUSThello then keeps their wins, \
+ties and losses against each AI and each other named player, with the best disk differential they \
+reached, in 'rusthello_profiles.txt'. Each match ends with the record of the named player against \
+their opponent, except for matches against remote players, who have no profile on this end. A color \
+(red, green, yellow, blue, magenta or cyan) can follow the name, e.g. 'h Alice cyan', to have the name \
+and the side shown in that color from then on.\n
+\tSaved matches can be replayed ('r FILE' in the main menu) move by move, forward and backward, \
+optionally with the AI analysing each move and pointing out mistakes.\n
+\tThe AIs judge positions by weighing corners, mobility, frontier disks, stability and parity. The \
+weights are read from 'rusthello_weights.txt' if there is one, and 'rusthello tune [ROUNDS]' improves \
+them by having the AI play against itself, writing the file back.\n
+\tThe AIs also follow the opening book 'rusthello_book.txt', if there is one. 'rusthello book FILES...' \
+builds it from saved matches (one per line, as moves or in GGF), and its 'variety' line sets how \
+much worse than the best known move a move may score and still be played, so that the AIs do not \
+always play the same openings.\n
+\tTo compare AIs, 'rusthello tournament GAMES A B' has two of them play each other without \
+showing the board, e.g. 'rusthello tournament 20 medium 0.5:my_weights.txt', where each AI is \
+given by its strength (weak, medium, strong or seconds per move) and optionally a weights file. \
+Games start from random openings, played once with each AI as Dark, and the results come with an \
+Elo estimate.\n
+\tFinally, 'rusthello engine' runs the AI alone, answering commands such as 'play b f5', 'genmove w' \
+or 'showboard' on the standard input and output, as in the Go Text Protocol, so that it can be \
+driven by other programs. Type 'list_commands' for the full list.\n
+\tAll the commands can also be piped in from a file, one per line, e.g. 'rusthello < game.txt' to \
+play a scripted match: RUSThello quits once the input is over.";
+
+/// Prints the rules, waits for Enter, then prints how to play RUSThello.
+pub fn help() -> io::Result<()> {
     header("REVERSI");
     println!("{}", HELP);
-    let _ = io::stdout().flush();
-    let mut input = String::new();
-    if io::stdin().read_line(&mut input).is_err() {
-        panic!("\tFailed to read input!");
-    }
+    try!(get_raw_user_input());
     header("RUSThello");
     println!("{}", RUSTHELLO);
+    Ok(())
 }
 
 pub fn credits() {
@@ -158,28 +271,55 @@ pub fn credits() {
     println!("\tReleased under the MIT license");
 }
 
-/// Reads user's input
-fn get_user_input() -> String {
+/// Reads user's input, as it was typed. Fails with `UnexpectedEof` once the input is closed (e.g. by Ctrl-D,
+/// or at the end of a script piped in), as there is nothing more to read.
+fn get_raw_user_input() -> io::Result<String> {
     let _ = io::stdout().flush();
     let mut input = String::new();
-    if io::stdin().read_line(&mut input).is_err() {
-        panic!("\tFailed to read input!");
+    if try!(io::stdin().read_line(&mut input)) == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the input was closed"));
+    }
+    Ok(input.trim().to_string())
+}
+
+/// Reads user's input, in lower case
+fn get_user_input() -> io::Result<String> {
+    get_raw_user_input().map(|input| input.to_lowercase())
+}
+
+/// Splits an input such as "save My Game.ggf" into a lower-case command and its (case-preserving) argument.
+fn split_command(input: &str) -> (String, &str) {
+    match input.find(char::is_whitespace) {
+        Some(space) => (input[..space].to_lowercase(), input[space..].trim()),
+        None => (input.to_lowercase(), ""),
     }
-    input = input.trim().to_lowercase();
-    input
 }
 
 /// It gets an input from the user and tries to parse it, then returns a Option<UserCommand>`.
 /// If the input is recognized as a legit command, it returns the relative `Option::Some(UserCommand)`.
 /// If the input is not recognized as a legit command, it returns a `Option::None`.
-pub fn input_main_menu() -> UserCommand {
+pub fn input_main_menu() -> io::Result<UserCommand> {
     print!("\tInsert input: ");
     loop {
-        match &*get_user_input() {
-            "n" | "new game" => return UserCommand::NewGame,
-            "h" | "help" => return UserCommand::Help,
-            "c" | "credits" => return UserCommand::Credits,
-            "q" | "quit" | "exit" => return UserCommand::Quit,
+        let raw_input = try!(get_raw_user_input());
+        match split_command(&raw_input) {
+            (ref command, path) if (command == "l" || command == "load") && !path.is_empty() => return Ok(UserCommand::Load(path.to_string())),
+            (ref command, path) if (command == "s" || command == "save") && !path.is_empty() => return Ok(UserCommand::Save(path.to_string())),
+            (ref command, path) if (command == "r" || command == "replay") && !path.is_empty() => return Ok(UserCommand::Replay(path.to_string())),
+            (ref command, path) if (command == "p" || command == "position") && !path.is_empty() => return Ok(UserCommand::NewGameFrom(path.to_string())),
+            (ref command, address) if (command == "j" || command == "join") && !address.is_empty() => return Ok(UserCommand::Join(address.to_string())),
+            (ref command, args) if command == "n" && !args.is_empty() => {
+                if let Some(new_game) = parse_new_game(args) {
+                    return Ok(new_game);
+                }
+            }
+            _ => {}
+        }
+        match &*raw_input.to_lowercase() {
+            "n" | "new game" => return Ok(UserCommand::NewGame(DEFAULT_BOARD_SIZE, None)),
+            "h" | "help" => return Ok(UserCommand::Help),
+            "c" | "credits" => return Ok(UserCommand::Credits),
+            "q" | "quit" | "exit" => return Ok(UserCommand::Quit),
             _ => {
                 print!("\tInvalid command! Try again: ");
                 continue;
@@ -188,19 +328,67 @@ pub fn input_main_menu() -> UserCommand {
     }
 }
 
-pub fn choose_new_player(side: Side) -> UserCommand {
+/// Parses the arguments of a new game, `[SIZE] [MINUTES+SECONDS]`, such as `6`, `5+3` or `10 1+0`.
+fn parse_new_game(args: &str) -> Option<UserCommand> {
+    let mut size = DEFAULT_BOARD_SIZE;
+    let mut time_control = None;
+    for (index, arg) in args.split_whitespace().enumerate() {
+        match (TimeControl::parse(arg), arg.parse()) {
+            (Some(control), _) if time_control.is_none() => time_control = Some(control),
+            (None, Ok(board_size)) if index == 0 => size = board_size,
+            _ => return None,
+        }
+    }
+    Some(UserCommand::NewGame(size, time_control))
+}
+
+pub fn choose_new_player(side: Side) -> io::Result<PlayerChoice> {
     match side {
         Side::Dark => print!("\t{}Dark{}  player: ", style::Bold, style::Reset),
         Side::Light => print!("\t{}Light{} player: ", style::Bold, style::Reset),
     }
     loop {
-        match &*get_user_input() {
-            "h" | "human" | "player" | "human player" => return UserCommand::HumanPlayer,
-            "w" | "weak" | "weak ai" => return UserCommand::AiWeak,
-            "m" | "medium" | "medium ai" => return UserCommand::AiMedium,
-            "s" | "strong" | "strong ai" => return UserCommand::AiStrong,
-            "q" | "quit" | "exit" => return UserCommand::Quit,
-            _ => {
+        let raw_input = try!(get_raw_user_input());
+        match &*raw_input.to_lowercase() {
+            "h" | "human" | "player" | "human player" => return Ok(PlayerChoice::HumanPlayer(None)),
+            "k" | "keys" | "cursor" => return Ok(PlayerChoice::HumanCursor(None)),
+            "w" | "weak" | "weak ai" => return Ok(PlayerChoice::AiWeak),
+            "m" | "medium" | "medium ai" => return Ok(PlayerChoice::AiMedium),
+            "s" | "strong" | "strong ai" => return Ok(PlayerChoice::AiStrong),
+            "q" | "quit" | "exit" => return Ok(PlayerChoice::Quit),
+            input => {
+                // Timed AI, e.g. "t 5" or "timed 5"
+                let mut words = input.split_whitespace();
+                if let (Some("t"), Some(secs)) | (Some("timed"), Some(secs)) = (words.next(), words.next()) {
+                    if let Ok(secs) = secs.parse::<u64>() {
+                        if secs > 0 && words.next().is_none() {
+                            return Ok(PlayerChoice::AiTimed(secs));
+                        }
+                    }
+                }
+                // Human player with a profile, e.g. "h Alice" or "k Alice cyan"
+                let (command, argument) = split_command(&raw_input);
+                let words: Vec<&str> = argument.split_whitespace().collect();
+                if !words.is_empty() && words.len() <= 2 && profiles::is_valid_name(words[0]) {
+                    let color = words.get(1).map(|color| Color::parse(color));
+                    if color != Some(None) {
+                        let profile = Some((words[0].to_string(), color.and_then(|color| color)));
+                        match &*command {
+                            "h" | "human" => return Ok(PlayerChoice::HumanPlayer(profile)),
+                            "k" | "keys" | "cursor" => return Ok(PlayerChoice::HumanCursor(profile)),
+                            _ => {}
+                        }
+                    }
+                }
+                // Remote player, e.g. "r 7777" or "remote 7777"
+                let mut words = input.split_whitespace();
+                if let (Some("r"), Some(port)) | (Some("remote"), Some(port)) = (words.next(), words.next()) {
+                    if let Ok(port) = port.parse::<u16>() {
+                        if words.next().is_none() {
+                            return Ok(PlayerChoice::Remote(port));
+                        }
+                    }
+                }
                 print!("\tInvalid command! Try again: ");
                 continue;
             }
@@ -208,45 +396,43 @@ pub fn choose_new_player(side: Side) -> UserCommand {
     }
 }
 
-/// It get_status a human player's input and convert it into a move.
+/// It get_status a human player's input and convert it into a move, showing the side in the color of the player's profile.
 /// If the move if illegal, it ask for another input until the given move is a legal one.
-pub fn human_make_move(turn: &Turn) -> Result<Action> {
+/// Only a closed input means that the player quit: other failures to read it are errors.
+pub fn human_make_move(turn: &Turn, color: Option<Color>) -> io::Result<Action> {
 
     if let Some(side) = turn.get_state() {
         match side {
-            Side::Dark => print!("\t{}Dark{}  moves: ", style::Bold, style::Reset),
-            Side::Light => print!("\t{}Light{} moves: ", style::Bold, style::Reset),
+            Side::Dark => print!("\t{}  moves: ", side_label(side, color)),
+            Side::Light => print!("\t{} moves: ", side_label(side, color)),
         }
     } else {
         unreachable!();
     }
 
     loop {
-        let input = &*get_user_input();
+        // Once the input is closed, there is nobody to play anymore
+        let raw_input = match get_raw_user_input() {
+            Ok(raw_input) => raw_input,
+            Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(PlayerAction::Other(OtherAction::Quit)),
+            Err(err) => return Err(err),
+        };
+        let (command, path) = split_command(&raw_input);
+        if command == "save" && !path.is_empty() {
+            return Ok(PlayerAction::Other(OtherAction::Save(path.to_string())));
+        }
+        let input = &*raw_input.to_lowercase();
         match input {
             "h" | "help" => return Ok(PlayerAction::Other(OtherAction::Help)),
             "u" | "undo" => return Ok(PlayerAction::Undo),
+            "r" | "redo" => return Ok(PlayerAction::Redo),
             "q" | "quit" => return Ok(PlayerAction::Other(OtherAction::Quit)),
-            _other_input => {
-                let mut row: Option<usize> = None;
-                let mut col: Option<usize> = None;
-
-                for curr_char in input.chars() {
-                    match curr_char {
-                        '1'...'8' => row = Some(curr_char as usize - '1' as usize),
-                        'a'...'h' => col = Some(curr_char as usize - 'a' as usize),
-                        _ => {}
-                    }
-                }
-
-                if row.is_none() || col.is_none() {
-                    print!("\tIllegal move, try again: ");
-                    continue;
-                } else {
-                    let coord = Coord::new(row.unwrap(), col.unwrap());
-                    if turn.check_move(coord).is_ok() {
-                        return Ok(PlayerAction::Move(coord));
-                    } else {
+            "hint" => return Ok(PlayerAction::Other(OtherAction::Hint)),
+            "a" | "analysis" => return Ok(PlayerAction::Other(OtherAction::Analysis)),
+            other_input => {
+                match other_input.parse::<Coord>() {
+                    Ok(coord) if turn.check_move(coord).is_ok() => return Ok(PlayerAction::Move(coord)),
+                    _ => {
                         print!("\tIllegal move, try again: ");
                         continue;
                     }
@@ -258,20 +444,51 @@ pub fn human_make_move(turn: &Turn) -> Result<Action> {
 
 /// draw_board draws the board (using text characters) in a pleasant-looking way, converting the board in a string (board_to_string) and then printing this.
 pub fn draw_board(turn: &Turn) {
-    let board = turn.get_board();
+    println!("{}", board_to_string(turn, turn.get_board(), None, &[]));
+}
+
+/// Draws the board with the time left to each side under its score.
+pub fn draw_board_with_clocks(turn: &Turn, clocks: &Clocks) {
+    println!("{}{}", board_to_string(turn, turn.get_board(), None, &[]), clocks_to_string(turn.get_size(), clocks));
+}
+
+/// Draws the board with the best moves found by the AI marked by their rank.
+pub fn draw_board_with_hints(turn: &Turn, hints: &[(Coord, Score)]) {
+    println!("{}", board_to_string(turn, turn.get_board(), None, &hinted_cells(hints)));
+}
+
+/// The cells of the best moves found by the AI, best first, as marked on the board.
+pub fn hinted_cells(hints: &[(Coord, Score)]) -> Vec<Coord> {
+    hints.iter().take(HINT_MOVES).map(|&(coord, _)| coord).collect()
+}
+
+/// Converts the disks on `board` into a string, together with the legal moves, the score and the side to move in `turn`.
+/// Usually `board` is the board of `turn`, but it may also be some intermediate state, e.g. while disks are flipping.
+/// The cell under the cursor, if any, is highlighted, and the cells in `hints` are marked by their rank.
+pub fn board_to_string(turn: &Turn, board: &Board, cursor: Option<Coord>, hints: &[Coord]) -> String {
+    let size = board.get_size();
+    // The frame is as wide as the cells plus the row references on both sides
+    let width = 2 * size + 9;
+    let blank_line: String = (0..width).map(|_| ' ').collect();
+    let letters: Vec<String> = (0..size).map(|col| ((b'A' + col as u8) as char).to_string()).collect();
+    let column_reference = format!("{}{}     {}     {}{}", color::Bg(color::LightGreen), color::Fg(color::Black), letters.join(" "), color::Fg(color::Reset), color::Bg(color::Reset));
+
     // Declare board_to_string and add column reference at the top
-    let mut board_to_string: String = format!("\n\t{}                         {}\n", color::Bg(color::LightGreen), color::Bg(color::Reset));
-    board_to_string.push_str(&format!("\t{}{}     A B C D E F G H     {}{}\n", color::Bg(color::LightGreen), color::Fg(color::Black), color::Fg(color::Reset), color::Bg(color::Reset)));
+    let mut board_to_string: String = format!("\n\t{}{}{}\n", color::Bg(color::LightGreen), blank_line, color::Bg(color::Reset));
+    board_to_string.push_str(&format!("\t{}\n", column_reference));
 
     // For every row…
-    for row in 0..BOARD_SIZE {
+    for row in 0..size {
         // Add a row reference to the left
-        board_to_string.push_str(&format!("\t{}  {}{}{} {}", color::Bg(color::LightGreen), color::Fg(color::Black), row + 1, color::Fg(color::Reset), color::Bg(color::Reset)));
+        board_to_string.push_str(&format!("\t{} {}{:>2}{} {}", color::Bg(color::LightGreen), color::Fg(color::Black), row + 1, color::Fg(color::Reset), color::Bg(color::Reset)));
         // Set background color to green
         board_to_string.push_str(&format!("{} ", color::Bg(color::Green)));
         // For every column, add the appropriate character depending on the content of the current cell
-        for col in 0..BOARD_SIZE {
+        for col in 0..size {
             let coord = Coord::new(row, col);
+            if cursor == Some(coord) {
+                board_to_string.push_str(&format!("{}", color::Bg(color::Yellow)));
+            }
             board_to_string.push_str(
 	            &match board.get_cell(coord).unwrap() {
 	                // Light and Dark cells are represented by white and black bullets
@@ -282,7 +499,10 @@ pub fn draw_board(turn: &Turn) {
 	                // An empty cell will display a plus or a multiplication sign if the current player can move in that cell
 	                // or a little central dot otherwise
 	                None => {
-						if turn.check_move(coord).is_ok() {
+						if let Some(rank) = hints.iter().position(|&hint| hint == coord) {
+                            // Hinted moves show their rank instead
+                            format!("{}{}{}{}{}", color::Fg(color::Yellow), style::Bold, rank + 1, style::NoBold, color::Fg(color::Reset))
+                        } else if turn.check_move(coord).is_ok() {
                             match turn.get_state() {
                                 Some(Side::Dark)  => format!("{}{}{}{}{}", color::Fg(color::LightBlack), style::Faint, LEGAL_MOVE, style::NoFaint, color::Fg(color::Reset)),
                                 Some(Side::Light) => format!("{}{}{}{}{}", color::Fg(color::White), style::Faint, LEGAL_MOVE, style::NoFaint, color::Fg(color::Reset)),
@@ -294,74 +514,163 @@ pub fn draw_board(turn: &Turn) {
 					}
 	            }
 			);
+            if cursor == Some(coord) {
+                board_to_string.push_str(&format!("{}", color::Bg(color::Green)));
+            }
             board_to_string.push(' ');
         }
         // Reset background color
         board_to_string.push_str(&format!("{}", color::Bg(color::Reset)));
 
         // Add a row reference to the right
-        board_to_string.push_str(&format!("{} {}{}{}  {}\n", color::Bg(color::LightGreen), color::Fg(color::Black), row + 1, color::Fg(color::Reset), color::Bg(color::Reset)));
+        board_to_string.push_str(&format!("{} {}{:<2}{} {}\n", color::Bg(color::LightGreen), color::Fg(color::Black), row + 1, color::Fg(color::Reset), color::Bg(color::Reset)));
     }
 
     // Add column reference at the bottom
-    board_to_string.push_str(&format!("\t{}{}     A B C D E F G H     {}{}", color::Bg(color::LightGreen), color::Fg(color::Black), color::Fg(color::Reset), color::Bg(color::Reset)));
-
-    // Print board
-    println!("{}", board_to_string);
+    board_to_string.push_str(&format!("\t{}\n", column_reference));
 
-    // Print current score and game info
+    // Add current score and game info
     let (score_dark, score_light) = turn.get_score();
-    println!("\t{}                         {}", color::Bg(color::LightGreen), color::Bg(color::Reset));
-    print!("\t{}{}       {:>2}{} ", color::Bg(color::LightGreen), color::Fg(color::Black), score_dark, color::Fg(color::Reset));
-    match turn.get_state() {
+    // The scores are centred around the disks
+    let score_width = (width - 5) / 2 - 1;
+    board_to_string.push_str(&format!("\t{}{}{}\n", color::Bg(color::LightGreen), blank_line, color::Bg(color::Reset)));
+    board_to_string.push_str(&format!("\t{}{}{:>4$}{} ", color::Bg(color::LightGreen), color::Fg(color::Black), score_dark, color::Fg(color::Reset), score_width));
+    board_to_string.push_str(&match turn.get_state() {
         Some(side) => match side {
             Side::Dark => {
-                print!("{}{}{}{}{}   {}{}{}",
+                format!("{}{}{}{}{}   {}{}{}",
                     color::Fg(color::Black), style::Blink, DARK_DISK, style::NoBlink, color::Fg(color::Reset),
-                    color::Fg(color::LightWhite), LIGHT_DISK, color::Fg(color::Reset));
+                    color::Fg(color::LightWhite), LIGHT_DISK, color::Fg(color::Reset))
             }
             Side::Light => {
-                print!("{}{}{}   {}{}{}{}{}",
+                format!("{}{}{}   {}{}{}{}{}",
                     color::Fg(color::Black), DARK_DISK, color::Fg(color::Reset),
-                    color::Fg(color::LightWhite), style::Blink, LIGHT_DISK, style::NoBlink, color::Fg(color::Reset));
+                    color::Fg(color::LightWhite), style::Blink, LIGHT_DISK, style::NoBlink, color::Fg(color::Reset))
             }
         },
         None => {
-            print!("{}{}{}   {}{}{}",
+            format!("{}{}{}   {}{}{}",
                 color::Fg(color::Black), DARK_DISK, color::Fg(color::Reset),
-                color::Fg(color::LightWhite), LIGHT_DISK, color::Fg(color::Reset));
+                color::Fg(color::LightWhite), LIGHT_DISK, color::Fg(color::Reset))
         }
-    };
-    println!(" {}{:<2}       {}{}\n", color::Fg(color::Black), score_light, color::Fg(color::Reset), color::Bg(color::Reset));
+    });
+    board_to_string.push_str(&format!(" {}{:<4$}{}{}\n", color::Fg(color::Black), score_light, color::Fg(color::Reset), color::Bg(color::Reset), score_width));
+    board_to_string
 }
 
-/// Prints a message with info on a move.
-pub fn move_message(side: Side, coord: Coord) {
+/// Converts the clocks into a line to go under the scores of a board of the given size.
+pub fn clocks_to_string(size: usize, clocks: &Clocks) -> String {
+    let width = 2 * size + 9;
+    let clock_width = (width - 5) / 2 - 1;
+    format!("\t{}{}{:>6$}       {:<6$}{}{}\n", color::Bg(color::LightGreen), color::Fg(color::Black),
+        duration_to_string(clocks.remaining(Side::Dark)), duration_to_string(clocks.remaining(Side::Light)),
+        color::Fg(color::Reset), color::Bg(color::Reset), clock_width)
+}
+
+/// Writes a time as minutes and seconds, e.g. `4:05`.
+fn duration_to_string(time: Duration) -> String {
+    format!("{}:{:02}", time.as_secs() / 60, time.as_secs() % 60)
+}
+
+/// Prints a message with info on a move, with the side in the color of the player's profile.
+pub fn move_message(side: Side, color: Option<Color>, coord: Coord) {
+    println!("{}", move_to_string(side, color, coord));
+}
+
+/// The message telling the move a side played
+pub fn move_to_string(side: Side, color: Option<Color>, coord: Coord) -> String {
     let char_col = (('a' as u8) + (coord.get_col() as u8)) as char;
     match side {
         Side::Dark => {
-            println!("\t{}Dark{}  moves: {}{}",
-                style::Bold, style::Reset,
+            format!("\t{}  moves: {}{}",
+                side_label(side, color),
                 char_col,
                 coord.get_row() + 1)
         }
         Side::Light => {
-            println!("\t{}Light{} moves: {}{}",
-                style::Bold, style::Reset,
+            format!("\t{} moves: {}{}",
+                side_label(side, color),
                 char_col,
                 coord.get_row() + 1)
         }
     }
 }
 
-/// Print a message to declare winner
-pub fn endgame_message(winner: Option<Side>) {
+/// The name of a side, in the color of its player's profile (see `player_label`)
+pub fn side_label(side: Side, color: Option<Color>) -> String {
+    match side {
+        Side::Dark => player_label("Dark", color),
+        Side::Light => player_label("Light", color),
+    }
+}
+
+/// The line naming both players above the board, given their names (see `player_label`)
+pub fn players_to_string(dark: &str, light: &str) -> String {
+    format!("\n\tDark: {}   Light: {}", dark, light)
+}
+
+/// Print a message to declare winner, given the names of the players (see `player_label`)
+pub fn endgame_message(winner: Option<Side>, dark: &str, light: &str) {
     match winner {
-        Some(Side::Dark) => println!("\t{}Dark wins{}!", style::Bold, style::Reset),
-        Some(Side::Light) => println!("\t{}Light wins{}!", style::Bold, style::Reset),
-        None => println!("\t{}Tie{}!", style::Bold, style::Reset),
+        Some(Side::Dark) => println!("\t{}Dark wins{}! Congratulations, {}!", style::Bold, style::Reset, dark),
+        Some(Side::Light) => println!("\t{}Light wins{}! Congratulations, {}!", style::Bold, style::Reset, light),
+        None => println!("\t{}Tie{}! Between {} and {}.", style::Bold, style::Reset, dark, light),
+    }
+
+}
+
+/// The name of a player, in bold and in the color of their profile, if any.
+pub fn player_label(name: &str, color: Option<Color>) -> String {
+    let color = match color {
+        Some(Color::Red) => color::Fg(color::LightRed).to_string(),
+        Some(Color::Green) => color::Fg(color::LightGreen).to_string(),
+        Some(Color::Yellow) => color::Fg(color::LightYellow).to_string(),
+        Some(Color::Blue) => color::Fg(color::LightBlue).to_string(),
+        Some(Color::Magenta) => color::Fg(color::LightMagenta).to_string(),
+        Some(Color::Cyan) => color::Fg(color::LightCyan).to_string(),
+        None => String::new(),
+    };
+    format!("{}{}{}{}{}", style::Bold, color, name, color::Fg(color::Reset), style::Reset)
+}
+
+/// Print how a player with a profile fared against an opponent so far (see `player_label` for their names)
+pub fn head_to_head_message(player: &str, opponent: &str, results: &Results) {
+    println!("\t{} against {}: {} won, {} tied, {} lost out of {} played.", player, opponent,
+        results.wins, results.ties, results.losses, results.games());
+    if let Some(best) = results.best {
+        println!("\tBest differential: {:+} disks.", best);
+    }
+}
+
+/// Print a message when the profiles file cannot be read, or written
+pub fn profiles_error_message(path: &str, err: &io::Error) {
+    println!("\tCould not use {}, statistics are not kept: {}", path, err);
+}
+
+/// Print a message when a side runs out of time, and loses
+pub fn time_out_message(side: Side) {
+    match side {
+        Side::Dark => println!("\t{}Dark{} ran out of time: {}Light wins{}!", style::Bold, style::Reset, style::Bold, style::Reset),
+        Side::Light => println!("\t{}Light{} ran out of time: {}Dark wins{}!", style::Bold, style::Reset, style::Bold, style::Reset),
     }
+}
+
+/// Print a message while waiting for a remote player to join, for at most `timeout` seconds
+pub fn hosting_message(port: u16, timeout: u64) {
+    println!("\tWaiting for the remote player to join on port {} (for up to {} minutes)...", port, timeout / 60);
+}
 
+/// Print a message when a connection to a remote player cannot be made
+pub fn network_error_message(err: &io::Error) {
+    println!("\tCannot play over the network: {}", err);
+}
+
+/// Print a message when the peer of a remote player went away or broke the rules
+pub fn disconnected_message(side: Side) {
+    match side {
+        Side::Dark => println!("\tThe connection to {}Dark{} was lost.", style::Bold, style::Reset),
+        Side::Light => println!("\tThe connection to {}Light{} was lost.", style::Bold, style::Reset),
+    }
 }
 
 /// Print a last message before a player quits the game
@@ -373,6 +682,21 @@ pub fn quitting_message(state: State) {
     }
 }
 
+/// Print which of 'undo' and 'redo' are possible, if any
+pub fn undo_redo_message(can_undo: bool, can_redo: bool) {
+    match (can_undo, can_redo) {
+        (true, true) => println!("\tYou can 'undo' or 'redo'."),
+        (true, false) => println!("\tYou can 'undo'."),
+        (false, true) => println!("\tYou can 'redo'."),
+        (false, false) => {}
+    }
+}
+
+/// Print a message when 'redo' is not possible
+pub fn no_redo_message() {
+    println!("\tThere is no move to redo.");
+}
+
 /// Print a last message when 'undo' is not possible
 pub fn no_undo_message(undecided: Side) {
     match undecided {
@@ -380,3 +704,175 @@ pub fn no_undo_message(undecided: Side) {
         Side::Light => println!("\tThere is no move {}Light{} can undo.", style::Bold, style::Reset),
     }
 }
+
+/// Print the outcome of saving a game
+pub fn save_message(path: &str, result: &io::Result<()>) {
+    match *result {
+        Ok(()) => println!("\tGame saved to {}.", path),
+        Err(ref err) => println!("\tCould not save the game to {}: {}", path, err),
+    }
+}
+
+/// Print a message when a saved game cannot be loaded
+pub fn load_error_message(path: &str, err: &io::Error) {
+    println!("\tCould not load a game from {}: {}", path, err);
+}
+
+/// Asks for the name of the file to save the game to
+pub fn input_file_name() -> io::Result<String> {
+    print!("\tSave the game to file: ");
+    get_raw_user_input()
+}
+
+/// Print a message when there is no game to save
+pub fn no_game_message() {
+    println!("\tThere is no match to save yet.");
+}
+
+/// Print a message when a match or a replay was broken off by an error
+pub fn game_error_message(err: &ReversiError) {
+    println!("\tThe match was broken off: {}", err);
+}
+
+/// Print a message when a new match cannot be played on the board size asked for
+pub fn board_size_message(err: &ReversiError) {
+    println!("\tCannot start a new match: {}", err);
+}
+
+const REPLAY_INFO: &'static str = "\n\n
+\tReplaying match...
+\tPress Enter or type 'n' to go to the next move, 'p' to the previous one.
+\tType 's' to go to the start, 'e' to the end.
+\tType 'a' to have the AI analyse each move (or to stop it).
+\tType 'q' to stop replaying.";
+
+pub fn replay_info() {
+    println!("{}", REPLAY_INFO);
+}
+
+/// Reads a command to move through a replay
+pub fn input_replay() -> io::Result<ReplayCommand> {
+    print!("\tReplay: ");
+    loop {
+        match &*try!(get_user_input()) {
+            "" | "n" | "next" => return Ok(ReplayCommand::Forward),
+            "p" | "previous" => return Ok(ReplayCommand::Back),
+            "s" | "start" => return Ok(ReplayCommand::First),
+            "e" | "end" => return Ok(ReplayCommand::Last),
+            "a" | "analysis" => return Ok(ReplayCommand::Analysis),
+            "q" | "quit" => return Ok(ReplayCommand::Quit),
+            _ => {
+                print!("\tInvalid command! Try again: ");
+                continue;
+            }
+        }
+    }
+}
+
+/// Print which move of a replay is shown
+pub fn replay_move_message(number: usize, total: usize, side: Side, coord: Coord) {
+    match side {
+        Side::Dark => println!("\tMove {} of {}: {}Dark{} played {}", number, total, style::Bold, style::Reset, coord),
+        Side::Light => println!("\tMove {} of {}: {}Light{} played {}", number, total, style::Bold, style::Reset, coord),
+    }
+}
+
+/// Print that a replay is at the starting position
+pub fn replay_start_message(total: usize) {
+    println!("\tStarting position, {} moves to go", total);
+}
+
+/// Print that a replay cannot go any further
+pub fn replay_bound_message() {
+    println!("\tThere are no more moves that way.");
+}
+
+/// A score of the AI in readable form, from the point of view of `side`.
+fn ai_score_to_string(score: Score, side: Side) -> String {
+    let sign = if side == Side::Light { 1 } else { -1 };
+    match score {
+        Score::Running(value) => format!("{:+.2}", value * sign as f64),
+        Score::Ended(diff) => format!("{:+} disks", diff * sign),
+    }
+}
+
+/// Print the best moves found by the AI, and the perfect play if the endgame could be solved
+pub fn hint_message(turn: &Turn, hints: &[(Coord, Score)], perfect: Option<Solution>) {
+    println!("{}", hint_to_string(turn, hints, perfect));
+}
+
+/// The best moves found by the AI with their scores, and the perfect move if the position was solved
+pub fn hint_to_string(turn: &Turn, hints: &[(Coord, Score)], perfect: Option<Solution>) -> String {
+    let side = turn.get_state().expect("Hints are given in running turns");
+    let best: Vec<String> = hints.iter().take(HINT_MOVES).enumerate()
+        .map(|(rank, &(coord, score))| format!("{}. {} ({})", rank + 1, coord, ai_score_to_string(score, side)))
+        .collect();
+    let mut message = format!("\tBest moves: {}", best.join(", "));
+    if let Some(solution) = perfect {
+        message.push_str(&format!("\n\tPerfect play: {}, ending {}",
+            solution.coord,
+            match solution.differential {
+                0 => "in a tie".to_string(),
+                diff if diff > 0 => format!("with a win by {} disks", diff),
+                diff => format!("with a loss by {} disks", -diff),
+            }));
+    }
+    message
+}
+
+/// Print a bar showing who is ahead according to the AI: Dark's share on the left, Light's on the right
+pub fn evaluation_bar(score: Score) {
+    println!("{}\n", evaluation_bar_to_string(score));
+}
+
+/// The bar printed by `evaluation_bar`
+pub fn evaluation_bar_to_string(score: Score) -> String {
+    // Light's share of the bar, between 0 and 1
+    let (share, label) = match score {
+        Score::Running(value) => {
+            let value = value.max(-1f64).min(1f64);
+            ((value + 1f64) / 2f64, if value < 0f64 { format!("Dark {:+.2}", -value) } else { format!("Light {:+.2}", value) })
+        }
+        Score::Ended(0) => (0.5f64, "tie".to_string()),
+        Score::Ended(diff) if diff > 0 => (1f64, format!("Light wins by {}", diff)),
+        Score::Ended(diff) => (0f64, format!("Dark wins by {}", -diff)),
+    };
+    let light = (share * BAR_WIDTH as f64).round() as usize;
+    let dark_bar: String = (light..BAR_WIDTH).map(|_| ' ').collect();
+    let light_bar: String = (0..light).map(|_| ' ').collect();
+    format!("\t{}{}{}{}{} {}", color::Bg(color::Black), dark_bar, color::Bg(color::LightWhite), light_bar, color::Bg(color::Reset), label)
+}
+
+/// Print whether moves are being analysed
+pub fn analysis_toggle_message(analysis: bool) {
+    println!("{}", analysis_toggle_to_string(analysis));
+}
+
+/// Whether moves are being analysed, as printed by `analysis_toggle_message`
+pub fn analysis_toggle_to_string(analysis: bool) -> String {
+    if analysis {
+        "\tThe AI analyses each move.".to_string()
+    } else {
+        "\tThe AI stops analysing moves.".to_string()
+    }
+}
+
+/// A search score in readable form: the final disk differential if it is known, a heuristic value otherwise.
+fn score_to_string(score: i32) -> String {
+    if score.abs() >= ENDGAME_SCALE {
+        format!("{:+} disks", score / ENDGAME_SCALE)
+    } else {
+        format!("{:+.2}", score as f64 / 1000f64)
+    }
+}
+
+/// Print the AI's opinion on a move, compared with the best one
+pub fn analysis_message(best: (Coord, i32), played: (Coord, i32), mistake: bool) {
+    print!("\tAI analysis: {} scores {}, the best move {} scores {}",
+        played.0, score_to_string(played.1), best.0, score_to_string(best.1));
+    if mistake {
+        println!(" - {}{}mistake!{}", style::Bold, color::Fg(color::Red), style::Reset);
+    } else {
+        println!("");
+    }
+}
@@ -11,122 +11,688 @@
 // External crates
 extern crate termion;
 extern crate rand;
+extern crate reversi;
 
 // Modules
 mod interface;
+mod cursor_interface;
 mod human_player;
 mod ai_player;
-mod reversi;
+mod search;
+mod endgame;
+mod evaluation;
+mod tuner;
+mod replay;
+mod engine;
+mod tournament;
+mod book;
+mod clock;
+mod network;
+mod profiles;
 
 use reversi::{ReversiError, Side};
+use reversi::board::Coord;
 use reversi::game::{PlayerAction, IsPlayer, Game};
-use interface::UserCommand;
-use std::result;
+use reversi::record::Record;
+use reversi::turn::Turn;
+use interface::{UserCommand, PlayerChoice};
+use ai_player::{AiPlayer, Strength};
+use evaluation::{Weights, WEIGHTS_FILE};
+use book::{Book, BOOK_FILE};
+use clock::{Clocks, TimeControl};
+use network::{Connection, RemotePlayer};
+use profiles::{Color, Profiles, PROFILES_FILE};
+use std::{env, error, fmt, io, process, result};
+use std::fs::File;
+use std::sync::{Arc, Mutex};
+use std::io::{Read, Write};
 use std::cmp::Ordering;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 pub enum OtherAction {
     Help,
+    /// Save the game to the given file.
+    Save(String),
+    /// Show the best moves according to the AI.
+    Hint,
+    /// Toggle showing who is ahead after every move.
+    Analysis,
     Quit,
+    /// The peer of a remote player went away, or broke the rules.
+    Disconnected,
+    /// The peer of a remote player saw the given side run out of time on its clocks.
+    TimeOut(Side),
+    /// The input of a human player could not be read, other than because it was closed.
+    InputError(io::Error),
 }
 
 pub type Action = PlayerAction<OtherAction>;
-pub type Result<T> = result::Result<T, ReversiError>;
+pub use reversi::Result;
+
+/// What can break off the menus and matches of RUSThello.
+#[derive(Debug)]
+pub enum RusthelloError {
+    /// The rules of the game were broken, e.g. by a corrupted record.
+    Game(ReversiError),
+    /// The user's input could not be read, e.g. because it was closed.
+    Input(io::Error),
+}
+
+impl From<ReversiError> for RusthelloError {
+    fn from(err: ReversiError) -> RusthelloError {
+        RusthelloError::Game(err)
+    }
+}
+
+impl From<io::Error> for RusthelloError {
+    fn from(err: io::Error) -> RusthelloError {
+        RusthelloError::Input(err)
+    }
+}
+
+impl fmt::Display for RusthelloError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RusthelloError::Game(ref err) => write!(f, "{}", err),
+            RusthelloError::Input(ref err) => write!(f, "Cannot read the input: {}", err),
+        }
+    }
+}
+
+impl error::Error for RusthelloError {
+    fn description(&self) -> &str {
+        match *self {
+            RusthelloError::Game(ref err) => err.description(),
+            RusthelloError::Input(ref err) => err.description(),
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            RusthelloError::Game(ref err) => Some(err),
+            RusthelloError::Input(ref err) => Some(err),
+        }
+    }
+}
+
+/// Hints tell the perfect move when the position can be solved exactly, that is with at most this many empty cells.
+const HINT_EMPTIES: u16 = 14;
+
+/// Rounds of self-play run by `rusthello tune` if not given.
+const TUNING_ROUNDS: u32 = 100;
 
 fn main() {
+    // `rusthello tune [ROUNDS]` tunes the evaluation weights instead of playing
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(|arg| &**arg) == Some("tune") {
+        let rounds = args.get(1).and_then(|rounds| rounds.parse().ok()).unwrap_or(TUNING_ROUNDS);
+        if let Err(err) = tuner::tune(WEIGHTS_FILE, rounds, random_seed()) {
+            println!("\tTuning failed: {}", err);
+        }
+        return;
+    }
+
+    // The AIs evaluate positions with the tuned weights, if any
+    let weights = match Weights::load(WEIGHTS_FILE) {
+        Ok(weights) => weights,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Weights::default(),
+        Err(err) => {
+            // On stderr, not to get mixed up with the answers of the engine mode
+            let _ = writeln!(io::stderr(), "\tCould not read {}, using default weights: {}", WEIGHTS_FILE, err);
+            Weights::default()
+        }
+    };
+
+    // `rusthello book FILES...` builds the opening book from recorded games
+    if args.first().map(|arg| &**arg) == Some("book") {
+        // Without games, the book already there would be replaced by an empty one
+        if args.len() < 2 {
+            println!("\tUsage: rusthello book FILES...");
+            println!("\twhere each file holds finished games, one per line, as moves or in GGF.");
+            return;
+        }
+        match book::build(&args[1..], BOOK_FILE) {
+            Ok(games) => println!("\tBuilt {} from {} games.", BOOK_FILE, games),
+            Err(err) => println!("\tBuilding the book failed: {}", err),
+        }
+        return;
+    }
+
+    // The AIs play the first moves from the opening book, if any
+    let book = Arc::new(match Book::load(BOOK_FILE) {
+        Ok(book) => book,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Book::default(),
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "\tCould not read {}, playing without a book: {}", BOOK_FILE, err);
+            Book::default()
+        }
+    });
+
+    // `rusthello engine` talks a text protocol on stdin/stdout, for GUIs and tournament managers
+    if args.first().map(|arg| &**arg) == Some("engine") {
+        if let Err(err) = engine::run(weights, book, random_seed()) {
+            let _ = writeln!(io::stderr(), "Engine failed: {}", err);
+        }
+        return;
+    }
+
+    // `rusthello tournament GAMES A B [OPENING_MOVES]` has two AI configurations play each other
+    if args.first().map(|arg| &**arg) == Some("tournament") {
+        match tournament_args(&args[1..], weights) {
+            Ok((first, second, games, opening_moves)) => {
+                if let Err(err) = tournament::run(&first, &second, games, opening_moves, random_seed()) {
+                    println!("\tTournament failed: {}", err);
+                }
+            }
+            Err(err) => {
+                println!("\t{}", err);
+                println!("\tUsage: rusthello tournament GAMES STRENGTH[:WEIGHTS_FILE] STRENGTH[:WEIGHTS_FILE] [OPENING_MOVES]");
+                println!("\twhere STRENGTH is weak, medium, strong or a time per move in seconds.");
+            }
+        }
+        return;
+    }
+
+    // The profiles of named players, unless their file cannot be read (so that it is not overwritten)
+    let mut profiles = match Profiles::load(PROFILES_FILE) {
+        Ok(profiles) => Some(profiles),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Some(Profiles::default()),
+        Err(err) => {
+            interface::profiles_error_message(PROFILES_FILE, &err);
+            None
+        }
+    };
+
     // Main intro
     interface::intro();
 
+    match main_menu(weights, &book, &mut profiles) {
+        Ok(()) => {}
+        // Nothing more to read, e.g. at the end of a script piped in: leave as if asked to
+        Err(RusthelloError::Input(ref err)) if err.kind() == io::ErrorKind::UnexpectedEof => interface::quitting_message(None),
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "\tRUSThello stopped: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Runs the main menu until the user quits.
+/// Matches broken off by an error are left behind, and only errors reading the input are returned.
+fn main_menu(weights: Weights, book: &Arc<Book>, profiles: &mut Option<Profiles>) -> result::Result<(), RusthelloError> {
+    // The last match played, which can be saved
+    let mut last_game: Option<Record> = None;
+
     loop {
         interface::main_menu();
 
-        match interface::input_main_menu() {
+        match try!(interface::input_main_menu()) {
             // Runs the game
-            UserCommand::NewGame => {
-                if play_game().is_err() {
-                    panic!("Match ended with an error!");
+            UserCommand::NewGame(size, time_control) => {
+                match Turn::new_game(size) {
+                    Ok(start) => try!(keep_record(play_game(weights, book, profiles, start, Vec::new(), time_control, None), &mut last_game)),
+                    Err(err) => interface::board_size_message(&err),
+                }
+            }
+            // Runs a game from a custom position
+            UserCommand::NewGameFrom(path) => {
+                match load_position(&path) {
+                    Ok(start) => try!(keep_record(play_game(weights, book, profiles, start, Vec::new(), None, None), &mut last_game)),
+                    Err(err) => interface::load_error_message(&path, &err),
+                }
+            }
+            // Plays the match offered by a remote host
+            UserCommand::Join(address) => {
+                match join(&address) {
+                    Ok((side, start, moves, time_control, connection)) => {
+                        try!(keep_record(play_game(weights, book, profiles, start, moves, time_control, Some((side, connection))), &mut last_game))
+                    }
+                    Err(err) => interface::network_error_message(&err),
+                }
+            }
+            // Resumes a saved game
+            UserCommand::Load(path) => {
+                match load_record(&path) {
+                    Ok(record) => try!(keep_record(play_game(weights, book, profiles, record.start, record.moves, None, None), &mut last_game)),
+                    Err(err) => interface::load_error_message(&path, &err),
+                }
+            }
+            // Replays a saved game
+            UserCommand::Replay(path) => {
+                match load_record(&path) {
+                    Ok(record) => {
+                        match replay::replay(&record, weights) {
+                            Ok(()) => {}
+                            Err(RusthelloError::Game(err)) => interface::game_error_message(&err),
+                            Err(err) => return Err(err),
+                        }
+                    }
+                    Err(err) => interface::load_error_message(&path, &err),
+                }
+            }
+            // Saves the last match
+            UserCommand::Save(path) => {
+                match last_game {
+                    Some(ref record) => interface::save_message(&path, &save_record(record, &path)),
+                    None => interface::no_game_message(),
                 }
             }
             // Prints help message
-            UserCommand::Help => interface::help(),
+            UserCommand::Help => try!(interface::help()),
             // Print credits
             UserCommand::Credits => interface::credits(),
             // Quit RUSThello
             UserCommand::Quit => {
                 interface::quitting_message(None);
-                break;
+                return Ok(());
             }
-            _ => panic!("Main got a user command it shouldn't have got!"),
         }
     }
 }
 
-fn play_game() -> Result<()> {
+/// Keeps the record of the match just played, or tells why it was broken off if the game went wrong.
+/// Errors reading the input are passed on.
+fn keep_record(result: result::Result<Option<Record>, RusthelloError>, last_game: &mut Option<Record>) -> result::Result<(), RusthelloError> {
+    match result {
+        Ok(record) => *last_game = record,
+        Err(RusthelloError::Game(err)) => interface::game_error_message(&err),
+        Err(err) => return Err(err),
+    }
+    Ok(())
+}
 
-    // Get the two players
-    interface::new_player_menu();
-    let mut dark_human = false;
-    let dark = match interface::choose_new_player(Side::Dark) {
-        UserCommand::Quit => return Ok(()),
-        UserCommand::HumanPlayer => {
-            dark_human = true;
-            Box::new(human_player::HumanPlayer) as Box<IsPlayer<OtherAction>>
-        }
-        UserCommand::AiWeak => Box::new(ai_player::AiPlayer::Weak) as Box<IsPlayer<OtherAction>>,
-        UserCommand::AiMedium => Box::new(ai_player::AiPlayer::Medium) as Box<IsPlayer<OtherAction>>,
-        UserCommand::AiStrong => Box::new(ai_player::AiPlayer::Strong) as Box<IsPlayer<OtherAction>>,
-        _ => panic!("Returned an invalid player choice"),
+/// Parses the arguments of `rusthello tournament`.
+fn tournament_args(args: &[String], weights: Weights) -> io::Result<(tournament::Contestant, tournament::Contestant, u32, usize)> {
+    if args.len() < 3 || args.len() > 4 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "wrong number of arguments"));
+    }
+    let games = match args[0].parse() {
+        Ok(games) if games > 0 => games,
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid number of games")),
     };
-    let mut light_human = false;
-    let light = match interface::choose_new_player(Side::Light) {
-        UserCommand::Quit => return Ok(()),
-        UserCommand::HumanPlayer => {
-            light_human = true;
-            Box::new(human_player::HumanPlayer) as Box<IsPlayer<OtherAction>>
-        }
-        UserCommand::AiWeak => Box::new(ai_player::AiPlayer::Weak) as Box<IsPlayer<OtherAction>>,
-        UserCommand::AiMedium => Box::new(ai_player::AiPlayer::Medium) as Box<IsPlayer<OtherAction>>,
-        UserCommand::AiStrong => Box::new(ai_player::AiPlayer::Strong) as Box<IsPlayer<OtherAction>>,
-        _ => panic!("Returned an invalid player choice"),
+    let first = try!(tournament::Contestant::parse(&args[1], weights));
+    let second = try!(tournament::Contestant::parse(&args[2], weights));
+    let opening_moves = match args.get(3) {
+        Some(moves) => try!(moves.parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid number of opening moves"))),
+        None => tournament::OPENING_MOVES,
     };
+    Ok((first, second, games, opening_moves))
+}
 
-    // Print commands info
-    interface::commands_info();
+/// A seed which changes every time it is asked for.
+fn random_seed() -> u32 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.subsec_nanos()).unwrap_or(0)
+}
+
+/// Writes a game record to a file: in GGF if its name ends with `.ggf`, as a plain transcript otherwise.
+/// Transcripts cannot tell where the game started, so they are refused for games not starting from the standard position.
+fn save_record(record: &Record, path: &str) -> io::Result<()> {
+    let text = if path.to_lowercase().ends_with(".ggf") {
+        try!(record.to_ggf().map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)))
+    } else if record.has_standard_start() {
+        record.to_transcript()
+    } else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "only GGF files ('.ggf') can record a custom start"));
+    };
+    File::create(path).and_then(|mut file| writeln!(file, "{}", text))
+}
+
+/// Reads a game record from a file, in GGF or as a plain transcript.
+fn load_record(path: &str) -> io::Result<Record> {
+    let mut text = String::new();
+    try!(File::open(path).and_then(|mut file| file.read_to_string(&mut text)));
+    Record::parse(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Connects to a remote host and reads the match it offers: the side to play, the starting turn, the moves already played
+/// and the time control, if any.
+fn join(address: &str) -> io::Result<(Side, Turn, Vec<Coord>, Option<TimeControl>, Connection)> {
+    let mut connection = try!(Connection::join(address));
+    let (side, start, moves, time_control) = try!(connection.accept_offer());
+    Ok((side, start, moves, time_control, connection))
+}
+
+/// Reads a starting position from a file, in the format of `Turn::from_position`.
+fn load_position(path: &str) -> io::Result<Turn> {
+    let mut text = String::new();
+    try!(File::open(path).and_then(|mut file| file.read_to_string(&mut text)));
+    Turn::from_position(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// The value of a position according to the AI: the score of the best move, or the final result once the game is ended.
+fn evaluate(adviser: &AiPlayer, turn: &Turn) -> Result<ai_player::Score> {
+    if turn.is_endgame() {
+        Ok(ai_player::Score::Ended(turn.get_score_diff()))
+    } else {
+        Ok(try!(adviser.score_moves(turn))[0].1)
+    }
+}
+
+/// How a player is named in game records.
+fn player_name(choice: &PlayerChoice) -> String {
+    match *choice {
+        PlayerChoice::HumanPlayer(Some((ref name, _))) | PlayerChoice::HumanCursor(Some((ref name, _))) => name.clone(),
+        PlayerChoice::HumanPlayer(None) | PlayerChoice::HumanCursor(None) => "Human".to_string(),
+        PlayerChoice::AiWeak => "Weak AI".to_string(),
+        PlayerChoice::AiMedium => "Medium AI".to_string(),
+        PlayerChoice::AiStrong => "Strong AI".to_string(),
+        PlayerChoice::AiTimed(secs) => format!("AI ({} s per move)", secs),
+        PlayerChoice::Remote(_) => "Remote player".to_string(),
+        PlayerChoice::Quit => String::new(),
+    }
+}
+
+/// How a player is named as an opponent in the profiles, if the results against it are kept.
+fn opponent_name(choice: &PlayerChoice) -> Option<String> {
+    match *choice {
+        PlayerChoice::HumanPlayer(Some((ref name, _))) | PlayerChoice::HumanCursor(Some((ref name, _))) => Some(name.clone()),
+        PlayerChoice::AiWeak => Some(profiles::ai_opponent(Strength::Weak)),
+        PlayerChoice::AiMedium => Some(profiles::ai_opponent(Strength::Medium)),
+        PlayerChoice::AiStrong => Some(profiles::ai_opponent(Strength::Strong)),
+        PlayerChoice::AiTimed(secs) => Some(profiles::ai_opponent(Strength::Timed(Duration::from_secs(secs)))),
+        _ => None,
+    }
+}
+
+/// Adds the result of a match to the profiles of its players and saves them, then shows how they fared against each other.
+/// The outcome and the disk differential are from Dark's point of view, with no differential for matches decided on time.
+fn record_result(profiles: &mut Option<Profiles>, dark: &Seat, light: &Seat, outcome: Ordering, differential: Option<i16>) {
+    let profiles = match *profiles {
+        Some(ref mut profiles) => profiles,
+        None => return,
+    };
+    let sides = [(dark, light, outcome, differential), (light, dark, outcome.reverse(), differential.map(|differential| -differential))];
+    let mut meetings = Vec::new();
+    for &(seat, other, outcome, differential) in &sides {
+        if let (&Some(ref name), &Some(ref opponent)) = (&seat.profile, &other.opponent) {
+            // Nobody keeps statistics against themselves
+            if name != opponent {
+                profiles.add_result(name, opponent, outcome, differential);
+                meetings.push((seat, other, name, opponent));
+            }
+        }
+    }
+    if meetings.is_empty() {
+        return;
+    }
+    if let Err(err) = profiles.save(PROFILES_FILE) {
+        interface::profiles_error_message(PROFILES_FILE, &err);
+    }
+    // Two named players share their head-to-head record, which is shown once
+    let (seat, other, name, opponent) = meetings[0];
+    let results = profiles.get(name).expect("The profile was entered").against(opponent);
+    interface::head_to_head_message(&seat.label, &other.label, &results);
+}
+
+/// Draws the board under the names of the players, with the clocks if the match is played on time.
+fn draw_board(players: &str, turn: &Turn, clocks: &Option<Arc<Mutex<Clocks>>>) {
+    print!("{}", players);
+    match *clocks {
+        Some(ref clocks) => interface::draw_board_with_clocks(turn, &clocks.lock().expect("Could not read the clocks")),
+        None => interface::draw_board(turn),
+    }
+}
+
+/// A player taking part in a match, with what `play_game` needs to know about it.
+struct Seat {
+    player: Box<IsPlayer<OtherAction>>,
+    /// How the player is named in game records.
+    name: String,
+    /// How the player is named in messages, in the color of their profile.
+    label: String,
+    /// The color of the player's profile, if any.
+    color: Option<Color>,
+    /// The name of the player's profile, if any.
+    profile: Option<String>,
+    /// How the player is named as an opponent in the profiles, if the results against it are kept.
+    opponent: Option<String>,
+    human: bool,
+    /// Whether the player uses the cursor interface.
+    cursor: bool,
+    /// The connection to the peer of a remote player.
+    connection: Option<Arc<Mutex<Connection>>>,
+}
+
+/// Plays a match from the given starting turn, after replaying the given moves if resuming a saved one,
+/// and returns its record unless it was not even started. With a time control, a side whose clock runs out loses.
+/// A match joined from a remote host comes with the side to play and the connection to the host, who plays the other side.
+fn play_game(weights: Weights, book: &Arc<Book>, profiles: &mut Option<Profiles>, start: Turn, moves: Vec<Coord>, time_control: Option<TimeControl>, joined: Option<(Side, Connection)>) -> result::Result<Option<Record>, RusthelloError> {
+
+    // Every game is different, but AIs are reproducible given their seeds
+    let seed = random_seed();
+
+    // The clocks are shared with the AIs, which budget their time from them
+    let clocks = time_control.map(|control| Arc::new(Mutex::new(Clocks::new(control))));
+    let new_ai = |strength: Strength, seed: u32| {
+        let player = AiPlayer::new(strength, seed).with_weights(weights).with_book(book.clone());
+        Box::new(match clocks {
+            Some(ref clocks) => player.with_clocks(clocks.clone()),
+            None => player,
+        }) as Box<IsPlayer<OtherAction>>
+    };
+
+    // What the cursor interface shows under the board
+    let status = Arc::new(Mutex::new(cursor_interface::Status::default()));
+
+    // Get the two players: the host of a joined match plays its side, the user chooses the others
+    let joined = joined.map(|(side, connection)| (side, Arc::new(Mutex::new(connection))));
+    interface::new_player_menu();
+    let mut seats: Vec<Seat> = Vec::with_capacity(2);
+    for &side in &[Side::Dark, Side::Light] {
+        if let Some((joined_side, ref connection)) = joined {
+            if joined_side != side {
+                seats.push(Seat {
+                    player: Box::new(RemotePlayer::new(connection.clone())),
+                    name: "Remote player".to_string(),
+                    label: interface::player_label("Remote player", None),
+                    color: None,
+                    profile: None,
+                    opponent: None,
+                    human: false,
+                    cursor: false,
+                    connection: Some(connection.clone()),
+                });
+                continue;
+            }
+        }
+        let choice = try!(interface::choose_new_player(side));
+        let name = player_name(&choice);
+        let opponent = opponent_name(&choice);
+        // Named players get their profile, with its color
+        let (profile, color) = match choice {
+            PlayerChoice::HumanPlayer(Some((ref name, color))) | PlayerChoice::HumanCursor(Some((ref name, color))) => {
+                match *profiles {
+                    Some(ref mut profiles) => {
+                        let color = profiles.enter(name, color).color;
+                        if let Err(err) = profiles.save(PROFILES_FILE) {
+                            interface::profiles_error_message(PROFILES_FILE, &err);
+                        }
+                        (Some(name.clone()), color)
+                    }
+                    None => (Some(name.clone()), color),
+                }
+            }
+            _ => (None, None),
+        };
+        let seed = seed.wrapping_add(seats.len() as u32);
+        let (player, human, cursor, connection) = match choice {
+            PlayerChoice::Quit => return Ok(None),
+            PlayerChoice::HumanPlayer(_) => (Box::new(human_player::HumanPlayer::new(color)) as Box<IsPlayer<OtherAction>>, true, false, None),
+            PlayerChoice::HumanCursor(_) => (Box::new(human_player::CursorPlayer::new(color, status.clone(), clocks.clone())) as Box<IsPlayer<OtherAction>>, true, true, None),
+            PlayerChoice::AiWeak => (new_ai(Strength::Weak, seed), false, false, None),
+            PlayerChoice::AiMedium => (new_ai(Strength::Medium, seed), false, false, None),
+            PlayerChoice::AiStrong => (new_ai(Strength::Strong, seed), false, false, None),
+            PlayerChoice::AiTimed(secs) => (new_ai(Strength::Timed(Duration::from_secs(secs)), seed), false, false, None),
+            PlayerChoice::Remote(port) => {
+                interface::hosting_message(port, network::HOST_TIMEOUT);
+                let connection = match Connection::host(port).and_then(|mut connection| {
+                    try!(connection.offer(side, &start, &moves, time_control));
+                    Ok(connection)
+                }) {
+                    Ok(connection) => Arc::new(Mutex::new(connection)),
+                    Err(err) => {
+                        interface::network_error_message(&err);
+                        return Ok(None);
+                    }
+                };
+                (Box::new(RemotePlayer::new(connection.clone())) as Box<IsPlayer<OtherAction>>, false, false, Some(connection))
+            }
+        };
+        seats.push(Seat {
+            player: player,
+            label: interface::player_label(&name, color),
+            color: color,
+            name: name,
+            profile: profile,
+            opponent: opponent,
+            human: human,
+            cursor: cursor,
+            connection: connection,
+        });
+    }
+    let (dark, light) = (&seats[0], &seats[1]);
+    let seat = |side: Side| match side {
+        Side::Dark => dark,
+        Side::Light => light,
+    };
+
+    // Both players are named above the board
+    let players = interface::players_to_string(&dark.label, &light.label);
+    status.lock().expect("Could not write the status").players = players.clone();
+
+    // Print commands info, unless moves are animated on a terminal
+    let cursor_ui = (dark.cursor || light.cursor) && cursor_interface::is_available();
+    if !cursor_ui {
+        interface::commands_info();
+    }
+
+    // The AI giving hints and analysing moves, playing it straight
+    let adviser = AiPlayer::new(Strength::Medium, seed.wrapping_add(2)).with_weights(weights).with_randomness(0f64);
+    let mut analysis = false;
 
     // Create a new game
-    let mut game = Game::new(&*dark, &*light);
+    let mut game = Game::starting_from(&*dark.player, &*light.player, start);
+    try!(game.play_moves(&moves));
 
     // Draw the current board and game info
-    interface::draw_board(game.get_current_turn());
+    draw_board(&players, game.get_current_turn(), &clocks);
 
     // Proceed with turn after turn till the game ends
     while !game.is_ended() {
         let state_side = game.get_current_state().unwrap();
-        match game.play_turn() {
+        if seat(state_side).human {
+            interface::undo_redo_message(game.can_undo(), game.can_redo());
+        }
+        if let Some(ref clocks) = clocks {
+            clocks.lock().expect("Could not read the clocks").start(state_side);
+        }
+        let result = game.play_turn();
+
+        // A move coming after the flag fell is not counted
+        if let Some(ref clocks) = clocks {
+            let mut clocks = clocks.lock().expect("Could not read the clocks");
+            let moved = match result {
+                Ok(PlayerAction::Move(_)) => true,
+                _ => false,
+            };
+            clocks.stop(moved);
+            if clocks.is_flagged(state_side) {
+                // The peer of a remote player ends the match too, whatever its own clocks say
+                if let Some(connection) = dark.connection.as_ref().or(light.connection.as_ref()) {
+                    let _ = connection.lock().expect("Could not use the connection").send_time_out(state_side);
+                }
+                interface::time_out_message(state_side);
+                record_result(profiles, dark, light, if state_side == Side::Dark { Ordering::Less } else { Ordering::Greater }, None);
+                let mut moves = game.get_moves();
+                if moved {
+                    moves.pop();
+                }
+                return Ok(Some(Record::new(&dark.name, &light.name, game.get_first_turn().clone(), moves)));
+            }
+        }
+
+        match result {
             Ok(action) => {
+                // The peer of the other side plays the same action on its own game
+                if let Some(ref connection) = seat(state_side.opposite()).connection {
+                    if connection.lock().expect("Could not use the connection").send_action(&action).is_err() {
+                        interface::disconnected_message(state_side.opposite());
+                        return Ok(Some(Record::new(&dark.name, &light.name, game.get_first_turn().clone(), game.get_moves())));
+                    }
+                }
+                // The screen of the cursor interface is redrawn from scratch, so it is told what to show
+                let mut status = status.lock().expect("Could not write the status");
                 match action {
                     PlayerAction::Move(coord) => {
-                        match state_side {
-                            Side::Dark => {
-                                if !dark_human {
-                                    interface::move_message(state_side, coord);
-                                }
-                            }
-                            Side::Light => {
-                                if !light_human {
-                                    interface::move_message(state_side, coord);
-                                }
-                            }
+                        if cursor_ui {
+                            let &(ref before, _) = game.get_history().last().expect("A move was just played");
+                            cursor_interface::animate_move(&players, before, coord, game.get_current_turn(), clocks.as_ref().map(|clocks| &**clocks));
+                        }
+                        if !seat(state_side).human {
+                            interface::move_message(state_side, seat(state_side).color, coord);
+                        }
+                        if !cursor_ui {
+                            draw_board(&players, game.get_current_turn(), &clocks);
+                        }
+                        status.clear();
+                        status.last_move = Some(interface::move_to_string(state_side, seat(state_side).color, coord));
+                        if analysis {
+                            let score = try!(evaluate(&adviser, game.get_current_turn()));
+                            interface::evaluation_bar(score);
+                            status.evaluation = Some(score);
+                        }
+                    }
+                    PlayerAction::Undo | PlayerAction::Redo => {
+                        draw_board(&players, game.get_current_turn(), &clocks);
+                        status.clear();
+                        if analysis {
+                            let score = try!(evaluate(&adviser, game.get_current_turn()));
+                            interface::evaluation_bar(score);
+                            status.evaluation = Some(score);
+                        }
+                    }
+                    PlayerAction::Other(OtherAction::Hint) => {
+                        let turn = game.get_current_turn();
+                        let hints = try!(adviser.score_moves(turn));
+                        let perfect = try!(endgame::Solver::new(HINT_EMPTIES).solve(turn));
+                        print!("{}", players);
+                        interface::draw_board_with_hints(turn, &hints);
+                        interface::hint_message(turn, &hints, perfect);
+                        status.hints = interface::hinted_cells(&hints);
+                        status.message = Some(interface::hint_to_string(turn, &hints, perfect));
+                    }
+                    PlayerAction::Other(OtherAction::Analysis) => {
+                        analysis = !analysis;
+                        interface::analysis_toggle_message(analysis);
+                        status.message = Some(interface::analysis_toggle_to_string(analysis));
+                        status.evaluation = None;
+                        if analysis {
+                            let score = try!(evaluate(&adviser, game.get_current_turn()));
+                            interface::evaluation_bar(score);
+                            status.evaluation = Some(score);
                         }
-                        interface::draw_board(game.get_current_turn());
                     }
-                    PlayerAction::Undo => interface::draw_board(game.get_current_turn()),
                     PlayerAction::Other(OtherAction::Help) => {
-                        interface::help();
-                        interface::draw_board(game.get_current_turn());
+                        try!(interface::help());
+                        draw_board(&players, game.get_current_turn(), &clocks);
+                    }
+                    PlayerAction::Other(OtherAction::Save(path)) => {
+                        let record = Record::new(&dark.name, &light.name, game.get_first_turn().clone(), game.get_moves());
+                        interface::save_message(&path, &save_record(&record, &path));
                     }
                     PlayerAction::Other(OtherAction::Quit) => {
                         interface::quitting_message(game.get_current_state());
-                        return Ok(());
+                        return Ok(Some(Record::new(&dark.name, &light.name, game.get_first_turn().clone(), game.get_moves())));
+                    }
+                    PlayerAction::Other(OtherAction::InputError(err)) => return Err(RusthelloError::Input(err)),
+                    PlayerAction::Other(OtherAction::Disconnected) => {
+                        interface::disconnected_message(state_side);
+                        return Ok(Some(Record::new(&dark.name, &light.name, game.get_first_turn().clone(), game.get_moves())));
+                    }
+                    PlayerAction::Other(OtherAction::TimeOut(side)) => {
+                        interface::time_out_message(side);
+                        record_result(profiles, dark, light, if side == Side::Dark { Ordering::Less } else { Ordering::Greater }, None);
+                        return Ok(Some(Record::new(&dark.name, &light.name, game.get_first_turn().clone(), game.get_moves())));
                     }
                 }
             }
@@ -135,18 +701,21 @@ fn play_game() -> Result<()> {
                     ReversiError::NoUndo => {
                         interface::no_undo_message(game.get_current_state().unwrap())
                     }
-                    _ => return Err(err),
+                    ReversiError::NoRedo => interface::no_redo_message(),
+                    _ => return Err(RusthelloError::Game(err)),
                 }
             }
         }
     }
 
     let (score_dark, score_light) = game.get_current_score();
-    interface::endgame_message(match score_dark.cmp(&score_light) {
+    let outcome = score_dark.cmp(&score_light);
+    interface::endgame_message(match outcome {
         Ordering::Greater => Some(Side::Dark),
         Ordering::Less => Some(Side::Light),
         Ordering::Equal => None,
-    });
+    }, &dark.label, &light.label);
+    record_result(profiles, dark, light, outcome, Some(score_dark as i16 - score_light as i16));
 
-    Ok(())
+    Ok(Some(Record::new(&dark.name, &light.name, game.get_first_turn().clone(), game.get_moves())))
 }
@@ -0,0 +1,264 @@
+//! Networked matches: the moves of a remote player arrive from its peer over TCP, as lines of text.
+//!
+//! The host waits for a peer to connect and opens with `rusthello 2`, then tells the side played by the peer
+//! (`side dark` or `side light`), the starting position (`start POSITION`, as in `Turn::from_position`)
+//! the moves already played (`moves f5d6c3`, or `moves -`) and the time control (`clock 5+3`, or `clock -`).
+//! The peer answers `ready`.
+//! Then both ends send `move CELL`, `undo`, `redo` and `quit` as their players act, and play them on their own game.
+//! A match on time ends with `timeout dark` or `timeout light` from the end whose clocks saw that side run out of time.
+//! Every move is checked on arrival: a peer sending anything unexpected is disconnected.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use reversi::{self, Side};
+use reversi::board::Coord;
+use reversi::game::{IsPlayer, PlayerAction};
+use reversi::record::{self, Record};
+use reversi::turn::Turn;
+use clock::TimeControl;
+use ::{Action, OtherAction};
+
+/// The first line sent by the host, naming the protocol and its version.
+const GREETING: &'static str = "rusthello 2";
+
+/// How long the host waits for a peer to join, in seconds.
+pub const HOST_TIMEOUT: u64 = 300;
+
+/// How often the host checks whether a peer joined, in milliseconds.
+const ACCEPT_INTERVAL: u64 = 100;
+
+/// A connection to the peer of a networked match.
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Connection {
+    /// Waits for a peer to connect on the given port, giving up after `HOST_TIMEOUT` seconds.
+    pub fn host(port: u16) -> io::Result<Connection> {
+        let listener = try!(TcpListener::bind(("0.0.0.0", port)));
+        // Waiting for a connection cannot time out by itself, so the listener is polled instead
+        try!(listener.set_nonblocking(true));
+        let deadline = Instant::now() + Duration::from_secs(HOST_TIMEOUT);
+        loop {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    try!(stream.set_nonblocking(false));
+                    return Connection::new(stream);
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    if Instant::now() >= deadline {
+                        return Err(io::Error::new(io::ErrorKind::TimedOut, "nobody joined in time"));
+                    }
+                    thread::sleep(Duration::from_millis(ACCEPT_INTERVAL));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Connects to a host, given as `HOST:PORT`.
+    pub fn join(address: &str) -> io::Result<Connection> {
+        Connection::new(try!(TcpStream::connect(address)))
+    }
+
+    fn new(stream: TcpStream) -> io::Result<Connection> {
+        Ok(Connection {
+            reader: BufReader::new(try!(stream.try_clone())),
+            writer: stream,
+        })
+    }
+
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        try!(writeln!(self.writer, "{}", line));
+        self.writer.flush()
+    }
+
+    /// Reads the next line from the peer, failing if it went away.
+    fn receive(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        if try!(self.reader.read_line(&mut line)) == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the connection was closed"));
+        }
+        Ok(line.trim().to_string())
+    }
+
+    /// Tells the peer about an action of a local player. Actions which do not change the game are not sent.
+    pub fn send_action(&mut self, action: &Action) -> io::Result<()> {
+        match *action {
+            PlayerAction::Move(coord) => self.send(&format!("move {}", coord)),
+            PlayerAction::Undo => self.send("undo"),
+            PlayerAction::Redo => self.send("redo"),
+            PlayerAction::Other(OtherAction::Quit) => self.send("quit"),
+            PlayerAction::Other(_) => Ok(()),
+        }
+    }
+
+    /// Tells the peer that a side ran out of time, which ends the match.
+    pub fn send_time_out(&mut self, side: Side) -> io::Result<()> {
+        self.send(match side {
+            Side::Dark => "timeout dark",
+            Side::Light => "timeout light",
+        })
+    }
+
+    /// Offers the match to the peer, who is to play `side` from `start` after `moves` with the given time control,
+    /// and waits for it to accept.
+    pub fn offer(&mut self, side: Side, start: &Turn, moves: &[Coord], time_control: Option<TimeControl>) -> io::Result<()> {
+        try!(self.send(GREETING));
+        try!(self.send(match side {
+            Side::Dark => "side dark",
+            Side::Light => "side light",
+        }));
+        try!(self.send(&format!("start {}", start.to_position())));
+        let transcript: String = moves.iter().map(|&coord| coord.to_string()).collect();
+        try!(self.send(&format!("moves {}", if transcript.is_empty() { "-" } else { &transcript })));
+        try!(self.send(&format!("clock {}", time_control.map_or("-".to_string(), |control| control.to_string()))));
+        match &*try!(self.receive()) {
+            "ready" => Ok(()),
+            _ => Err(invalid("the peer did not accept the match")),
+        }
+    }
+
+    /// Reads the match offered by the host: the side to play, the starting turn, the moves already played
+    /// and the time control, if any.
+    pub fn accept_offer(&mut self) -> io::Result<(Side, Turn, Vec<Coord>, Option<TimeControl>)> {
+        if try!(self.receive()) != GREETING {
+            return Err(invalid("the host does not speak the RUSThello protocol"));
+        }
+        let side = match &*try!(self.receive()) {
+            "side dark" => Side::Dark,
+            "side light" => Side::Light,
+            _ => return Err(invalid("invalid side")),
+        };
+        let start = match &*try!(self.receive()) {
+            line if line.starts_with("start ") => {
+                try!(Turn::from_position(&line[6..]).map_err(|err| invalid(&err.to_string())))
+            }
+            _ => return Err(invalid("invalid starting position")),
+        };
+        let moves = match &*try!(self.receive()) {
+            "moves -" => Vec::new(),
+            line if line.starts_with("moves ") => {
+                try!(record::parse_moves(&line[6..]).map_err(|_| invalid("invalid moves")))
+            }
+            _ => return Err(invalid("invalid moves")),
+        };
+        // The moves are played from the starting position received, which may be any
+        try!(Record::new("", "", start.clone(), moves.clone()).turns().map_err(|_| invalid("invalid moves")));
+        let time_control = match &*try!(self.receive()) {
+            "clock -" => None,
+            line if line.starts_with("clock ") => {
+                Some(try!(TimeControl::parse(&line[6..]).ok_or(invalid("invalid time control"))))
+            }
+            _ => return Err(invalid("invalid time control")),
+        };
+        try!(self.send("ready"));
+        Ok((side, start, moves, time_control))
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// A player whose actions arrive from the peer.
+pub struct RemotePlayer {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl RemotePlayer {
+    pub fn new(connection: Arc<Mutex<Connection>>) -> RemotePlayer {
+        RemotePlayer {
+            connection: connection,
+        }
+    }
+}
+
+impl IsPlayer<OtherAction> for RemotePlayer {
+    /// Waits for the peer's next action. Illegal moves and lost connections end the match as a disconnection.
+    fn make_move(&self, turn: &Turn) -> reversi::Result<Action> {
+        let mut connection = self.connection.lock().expect("Could not use the connection");
+        let line = match connection.receive() {
+            Ok(line) => line,
+            Err(_) => return Ok(PlayerAction::Other(OtherAction::Disconnected)),
+        };
+        let mut words = line.split_whitespace();
+        Ok(match (words.next(), words.next(), words.next()) {
+            (Some("move"), Some(cell), None) => {
+                match cell.parse::<Coord>() {
+                    Ok(coord) if turn.check_move(coord).is_ok() => PlayerAction::Move(coord),
+                    _ => {
+                        let _ = connection.send("error illegal move");
+                        PlayerAction::Other(OtherAction::Disconnected)
+                    }
+                }
+            }
+            (Some("undo"), None, None) => PlayerAction::Undo,
+            (Some("redo"), None, None) => PlayerAction::Redo,
+            (Some("quit"), None, None) => PlayerAction::Other(OtherAction::Quit),
+            (Some("timeout"), Some("dark"), None) => PlayerAction::Other(OtherAction::TimeOut(Side::Dark)),
+            (Some("timeout"), Some("light"), None) => PlayerAction::Other(OtherAction::TimeOut(Side::Light)),
+            _ => {
+                let _ = connection.send("error unknown command");
+                PlayerAction::Other(OtherAction::Disconnected)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::time::Duration;
+    use reversi::Side;
+    use reversi::turn::Turn;
+    use clock::TimeControl;
+    use super::Connection;
+
+    /// A host and a peer connected to each other.
+    fn connect() -> (Connection, Connection) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let peer = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (host, _) = listener.accept().unwrap();
+        (Connection::new(host).unwrap(), Connection::new(peer).unwrap())
+    }
+
+    #[test]
+    fn offers_from_any_start_are_accepted() {
+        // A 6x6 board, where the moves would be illegal from the standard start
+        let start = Turn::new_game(6).unwrap();
+        let first = start.get_legal_moves()[0];
+        let second = start.make_move(first).unwrap().get_legal_moves()[0];
+        let moves = vec![first, second];
+        let control = TimeControl { base: Duration::from_secs(60), increment: Duration::from_secs(2) };
+
+        let (mut host, mut peer) = connect();
+        let offered = (start.clone(), moves.clone());
+        let host = thread::spawn(move || host.offer(Side::Light, &offered.0, &offered.1, Some(control)));
+        let (side, received, received_moves, time_control) = peer.accept_offer().unwrap();
+        host.join().unwrap().unwrap();
+
+        assert_eq!(side, Side::Light);
+        assert_eq!(received.to_position(), start.to_position());
+        assert_eq!(received_moves, moves);
+        assert_eq!(time_control, Some(control));
+    }
+
+    #[test]
+    fn illegal_moves_are_refused() {
+        let (mut host, mut peer) = connect();
+        // f5 is legal from the standard start only
+        let start = Turn::new_game(4).unwrap();
+        let host = thread::spawn(move || {
+            let _ = host.offer(Side::Dark, &start, &["f5".parse().unwrap()], None);
+        });
+        assert!(peer.accept_offer().is_err());
+        drop(peer);
+        host.join().unwrap();
+    }
+}
@@ -0,0 +1,284 @@
+//! Player profiles: named human players, with the color their name is shown in and their results against
+//! each opponent they met, kept from one session to the next.
+//!
+//! The profiles file holds a line `profile NAME [COLOR]` for each profile, followed by its results:
+//! `vs OPPONENT 5 1 3 +12` means 5 wins, 1 tie and 3 losses against OPPONENT, with a best disk differential of 12.
+//! An opponent is either another profile or an AI: `ai:weak`, `ai:medium`, `ai:strong`, or e.g. `ai:5s` for an AI
+//! thinking 5 seconds per move. Empty lines and lines starting with `#` are ignored.
+
+use std::cmp::{self, Ordering};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use ai_player::Strength;
+
+/// The file the profiles are read from and written to.
+pub const PROFILES_FILE: &'static str = "rusthello_profiles.txt";
+
+/// The colors a profile's name can be shown in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+/// Lists all colors from `Color`.
+pub const COLORS: [Color; 6] = [Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::Magenta, Color::Cyan];
+
+impl Color {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Color::Red => "red",
+            Color::Green => "green",
+            Color::Yellow => "yellow",
+            Color::Blue => "blue",
+            Color::Magenta => "magenta",
+            Color::Cyan => "cyan",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Color> {
+        COLORS.iter().cloned().find(|color| color.name() == name.to_lowercase())
+    }
+}
+
+/// Results of the games played against an opponent.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Results {
+    pub wins: u32,
+    pub ties: u32,
+    pub losses: u32,
+    /// The best difference between one's disks and the opponent's at the end of a game, if any was played out.
+    pub best: Option<i16>,
+}
+
+impl Results {
+    pub fn games(&self) -> u32 {
+        self.wins + self.ties + self.losses
+    }
+
+    /// Adds a game won, tied or lost, with its disk differential unless it was decided on time.
+    fn add(&mut self, outcome: Ordering, differential: Option<i16>) {
+        match outcome {
+            Ordering::Greater => self.wins += 1,
+            Ordering::Equal => self.ties += 1,
+            Ordering::Less => self.losses += 1,
+        }
+        if let Some(differential) = differential {
+            self.best = Some(self.best.map_or(differential, |best| cmp::max(best, differential)));
+        }
+    }
+}
+
+/// A named human player.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub color: Option<Color>,
+    /// The results against each opponent, in the order they were first met.
+    results: Vec<(String, Results)>,
+}
+
+impl Profile {
+    fn new(name: &str) -> Profile {
+        Profile {
+            name: name.to_string(),
+            color: None,
+            results: Vec::new(),
+        }
+    }
+
+    /// The results against an opponent, all zero if they never met.
+    pub fn against(&self, opponent: &str) -> Results {
+        self.results.iter().find(|&&(ref name, _)| name == opponent).map(|&(_, results)| results).unwrap_or(Results::default())
+    }
+
+    fn results_mut(&mut self, opponent: &str) -> &mut Results {
+        match self.results.iter().position(|&(ref name, _)| name == opponent) {
+            Some(index) => &mut self.results[index].1,
+            None => {
+                self.results.push((opponent.to_string(), Results::default()));
+                &mut self.results.last_mut().expect("The results were just added").1
+            }
+        }
+    }
+}
+
+/// Returns true if the name can be given to a profile: a single word of letters, digits, `-` and `_`.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// How an AI of the given strength is named as an opponent in the profiles file.
+pub fn ai_opponent(strength: Strength) -> String {
+    match strength {
+        Strength::Weak => "ai:weak".to_string(),
+        Strength::Medium => "ai:medium".to_string(),
+        Strength::Strong => "ai:strong".to_string(),
+        Strength::Timed(time) => format!("ai:{}s", time.as_secs()),
+    }
+}
+
+/// All the profiles known.
+#[derive(Debug, Clone, Default)]
+pub struct Profiles {
+    profiles: Vec<Profile>,
+}
+
+impl Profiles {
+    /// Reads the profiles from a file, in the format described above.
+    pub fn load(path: &str) -> io::Result<Profiles> {
+        let mut text = String::new();
+        try!(File::open(path).and_then(|mut file| file.read_to_string(&mut text)));
+
+        let mut profiles = Profiles::default();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[0] {
+                "profile" if (words.len() == 2 || words.len() == 3) && is_valid_name(words[1]) => {
+                    let mut profile = Profile::new(words[1]);
+                    if let Some(color) = words.get(2) {
+                        profile.color = Some(try!(Color::parse(color).ok_or_else(|| invalid(format!("invalid color '{}'", color)))));
+                    }
+                    profiles.profiles.push(profile);
+                }
+                "vs" if words.len() == 6 => {
+                    let counts: Vec<u32> = words[2..5].iter().filter_map(|word| word.parse().ok()).collect();
+                    let best = match words[5] {
+                        "-" => None,
+                        best => Some(try!(best.trim_left_matches('+').parse().map_err(|_| invalid(format!("invalid line '{}'", line))))),
+                    };
+                    if counts.len() != 3 {
+                        return Err(invalid(format!("invalid line '{}'", line)));
+                    }
+                    let profile = try!(profiles.profiles.last_mut().ok_or_else(|| invalid(format!("results of nobody '{}'", line))));
+                    profile.results.push((words[1].to_string(), Results { wins: counts[0], ties: counts[1], losses: counts[2], best: best }));
+                }
+                _ => return Err(invalid(format!("invalid line '{}'", line))),
+            }
+        }
+        Ok(profiles)
+    }
+
+    /// Writes the profiles to a file, in the format read by `load`.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut file = try!(File::create(path));
+        try!(writeln!(file, "# RUSThello profiles: name and color, then opponents with wins, ties, losses, best differential"));
+        for profile in &self.profiles {
+            match profile.color {
+                Some(color) => try!(writeln!(file, "profile {} {}", profile.name, color.name())),
+                None => try!(writeln!(file, "profile {}", profile.name)),
+            }
+            for &(ref opponent, results) in &profile.results {
+                let best = results.best.map_or("-".to_string(), |best| format!("{:+}", best));
+                try!(writeln!(file, "vs {} {} {} {} {}", opponent, results.wins, results.ties, results.losses, best));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    /// Returns the profile with the given name, which is created if new. Its color is changed if one is given.
+    pub fn enter(&mut self, name: &str, color: Option<Color>) -> &Profile {
+        let index = match self.profiles.iter().position(|profile| profile.name == name) {
+            Some(index) => index,
+            None => {
+                self.profiles.push(Profile::new(name));
+                self.profiles.len() - 1
+            }
+        };
+        if color.is_some() {
+            self.profiles[index].color = color;
+        }
+        &self.profiles[index]
+    }
+
+    /// Adds the result of a game to a profile, from its point of view: whether it won, tied or lost,
+    /// and the disk differential at the end unless the game was decided on time.
+    /// Games between two profiles must be added to both of them.
+    pub fn add_result(&mut self, name: &str, opponent: &str, outcome: Ordering, differential: Option<i16>) {
+        if let Some(profile) = self.profiles.iter_mut().find(|profile| profile.name == name) {
+            profile.results_mut(opponent).add(outcome, differential);
+        }
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use std::env;
+    use std::fs;
+    use std::process;
+    use std::time::Duration;
+    use ai_player::Strength;
+    use super::*;
+
+    #[test]
+    fn results_count_wins_ties_and_losses() {
+        let mut results = Results::default();
+        results.add(Ordering::Greater, Some(12));
+        results.add(Ordering::Less, Some(-4));
+        results.add(Ordering::Equal, Some(0));
+        results.add(Ordering::Less, None);
+        results.add(Ordering::Greater, Some(30));
+        assert_eq!(results, Results { wins: 2, ties: 1, losses: 2, best: Some(30) });
+        assert_eq!(results.games(), 5);
+    }
+
+    #[test]
+    fn profiles_are_read_back_from_their_file() {
+        let mut profiles = Profiles::default();
+        profiles.enter("Alice", Some(Color::Cyan));
+        profiles.enter("Bob", None);
+        profiles.add_result("Alice", &ai_opponent(Strength::Strong), Ordering::Less, Some(-10));
+        profiles.add_result("Alice", &ai_opponent(Strength::Timed(Duration::from_secs(5))), Ordering::Less, None);
+        profiles.add_result("Alice", "Bob", Ordering::Greater, Some(6));
+        profiles.add_result("Bob", "Alice", Ordering::Less, Some(-6));
+
+        // Named after the process, so that simultaneous test runs do not share the file
+        let path = env::temp_dir().join(format!("rusthello_profiles_{}.txt", process::id()));
+        let path = path.to_str().unwrap();
+        profiles.save(path).unwrap();
+        let loaded = Profiles::load(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        let alice = loaded.get("Alice").unwrap();
+        assert_eq!(alice.color, Some(Color::Cyan));
+        assert_eq!(alice.against("ai:strong"), Results { wins: 0, ties: 0, losses: 1, best: Some(-10) });
+        assert_eq!(alice.against("ai:5s"), Results { wins: 0, ties: 0, losses: 1, best: None });
+        assert_eq!(alice.against("Bob"), Results { wins: 1, ties: 0, losses: 0, best: Some(6) });
+        assert_eq!(alice.against("ai:weak"), Results::default());
+        let bob = loaded.get("Bob").unwrap();
+        assert_eq!(bob.color, None);
+        assert_eq!(bob.against("Alice").losses, 1);
+    }
+
+    #[test]
+    fn entering_a_profile_keeps_its_results() {
+        let mut profiles = Profiles::default();
+        profiles.enter("Alice", None);
+        profiles.add_result("Alice", "ai:weak", Ordering::Greater, Some(20));
+        assert_eq!(profiles.enter("Alice", Some(Color::Red)).against("ai:weak").wins, 1);
+        assert_eq!(profiles.enter("Alice", None).color, Some(Color::Red));
+        assert!(profiles.get("Bob").is_none());
+    }
+
+    #[test]
+    fn names_are_single_words() {
+        assert!(is_valid_name("Alice"));
+        assert!(is_valid_name("player_2"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("ai:weak"));
+        assert!(!is_valid_name("Alice Smith"));
+    }
+}
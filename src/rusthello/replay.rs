@@ -0,0 +1,71 @@
+//! Step-through replay of saved games.
+//! The user moves forward and backward through the positions of a game and may have the AI analyse each move,
+//! so that mistakes are highlighted.
+
+use std::result;
+use std::time::Duration;
+use reversi::game::Game;
+use reversi::record::Record;
+use human_player::HumanPlayer;
+use search::Searcher;
+use evaluation::Weights;
+use interface::{self, ReplayCommand};
+use ::{OtherAction, RusthelloError};
+
+/// Time spent analysing each move, in milliseconds.
+const ANALYSIS_TIME: u64 = 500;
+
+/// A move is a mistake if it scores worse than the best one by more than this.
+const MISTAKE_MARGIN: i32 = 100;
+
+/// Replays a game from its record, until the user quits.
+pub fn replay(record: &Record, weights: Weights) -> result::Result<(), RusthelloError> {
+    // Nobody is asked to move during a replay
+    let nobody = HumanPlayer::new(None);
+    let mut game: Game<OtherAction, _, _> = Game::starting_from(&nobody, &nobody, record.start.clone());
+    try!(game.play_moves(&record.moves));
+    while game.step_back().is_ok() {}
+
+    let mut analysis = false;
+    interface::replay_info();
+
+    loop {
+        interface::draw_board(game.get_current_turn());
+        match game.get_history().last() {
+            Some(&(ref turn, coord)) => {
+                let side = turn.get_state().expect("Moves are played in running turns");
+                interface::replay_move_message(game.get_history().len(), record.moves.len(), side, coord);
+                if analysis {
+                    let scores = try!(Searcher::new(Duration::from_millis(ANALYSIS_TIME), weights).score_moves(turn));
+                    let best = scores[0];
+                    let played = *scores.iter().find(|&&(other, _)| other.get_row_col() == coord.get_row_col())
+                        .expect("The move played is legal");
+                    interface::analysis_message(best, played, best.1 - played.1 > MISTAKE_MARGIN);
+                }
+            }
+            None => interface::replay_start_message(record.moves.len()),
+        }
+
+        loop {
+            match try!(interface::input_replay()) {
+                ReplayCommand::Forward => if game.step_forward().is_ok() { break },
+                ReplayCommand::Back => if game.step_back().is_ok() { break },
+                ReplayCommand::First => {
+                    while game.step_back().is_ok() {}
+                    break;
+                }
+                ReplayCommand::Last => {
+                    while game.step_forward().is_ok() {}
+                    break;
+                }
+                ReplayCommand::Analysis => {
+                    analysis = !analysis;
+                    interface::analysis_toggle_message(analysis);
+                    break;
+                }
+                ReplayCommand::Quit => return Ok(()),
+            }
+            interface::replay_bound_message();
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! Negamax alpha-beta search with iterative deepening, move ordering, a transposition table and a time limit.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use reversi;
+use reversi::bitboard::{self, Bits};
+use reversi::board::Coord;
+use reversi::turn::Turn;
+use evaluation::Weights;
+use ::Result;
+
+/// Scores of ended games are the disk differential times this factor, so they outweigh any heuristic evaluation.
+pub const ENDGAME_SCALE: i32 = 10000;
+
+/// Larger than any score.
+const INFINITY: i32 = 1000000;
+
+/// How many nodes are visited between two checks of the clock.
+const CLOCK_CHECK: u64 = 1024;
+
+/// The transposition table is cleared when it grows past this many entries.
+const TABLE_SIZE: usize = 1 << 20;
+
+/// Static ordering of the cells: corners first, then edges, then the rest, X-squares and C-squares last.
+/// On the standard board, this gives:
+///
+/// ```text
+///  9 -2  5  4  4  5 -2  9
+/// -2 -5  0  0  0  0 -5 -2
+///  5  0  3  2  2  3  0  5
+///  4  0  2  0  0  2  0  4
+/// ...
+/// ```
+fn cell_priority(coord: Coord, size: usize) -> i8 {
+    // Distances from the closest horizontal and vertical edges, the smallest first
+    let row = cmp::min(coord.get_row(), size - 1 - coord.get_row());
+    let col = cmp::min(coord.get_col(), size - 1 - coord.get_col());
+    match (cmp::min(row, col), cmp::max(row, col)) {
+        (0, 0) => 9,
+        (0, 1) => -2,
+        (0, 2) => 5,
+        (0, _) => 4,
+        (1, 1) => -5,
+        (1, _) => 0,
+        (2, 2) => 3,
+        (2, _) => 2,
+        _ => 0,
+    }
+}
+
+/// How a stored value relates to the true value of a position.
+#[derive(Clone, Copy, PartialEq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+/// An entry of the transposition table.
+#[derive(Clone, Copy)]
+struct Entry {
+    depth: u8,
+    value: i32,
+    bound: Bound,
+    best: Option<u8>,
+}
+
+/// The search was interrupted because time ran out.
+struct Timeout;
+
+/// A searcher holds the state of a search: its deadline, evaluation weights, counters and the transposition table.
+pub struct Searcher {
+    deadline: Instant,
+    weights: Weights,
+    nodes: u64,
+    table: HashMap<(Bits, Bits, bool), Entry>,
+}
+
+/// Index of a cell in bitboards.
+fn index(coord: Coord) -> u8 {
+    bitboard::index(coord) as u8
+}
+
+/// Key of a position in the transposition table.
+fn key(turn: &Turn, side: reversi::Side) -> (Bits, Bits, bool) {
+    let board = turn.get_board();
+    (board.get_bits(reversi::Side::Dark), board.get_bits(reversi::Side::Light), side == reversi::Side::Dark)
+}
+
+/// Evaluates a running position from the point of view of `side`.
+fn evaluate(turn: &Turn, side: reversi::Side, weights: &Weights) -> Result<i32> {
+    let light_score = (try!(weights.evaluate(turn)) * 1000f64) as i32;
+    Ok(match side {
+        reversi::Side::Light => light_score,
+        reversi::Side::Dark => -light_score,
+    })
+}
+
+/// Searching deeper than the number of empty cells is pointless.
+fn max_depth(turn: &Turn) -> u8 {
+    cmp::min(turn.get_empty_cells(), u8::max_value() as u16 - 1) as u8
+}
+
+/// Score of an ended game from the point of view of `side`.
+fn final_score(turn: &Turn, side: reversi::Side) -> i32 {
+    let diff = turn.get_score_diff() as i32 * ENDGAME_SCALE;
+    match side {
+        reversi::Side::Light => diff,
+        reversi::Side::Dark => -diff,
+    }
+}
+
+impl Searcher {
+    /// Creates a searcher which has to answer within `limit`, evaluating positions with the given weights.
+    pub fn new(limit: Duration, weights: Weights) -> Searcher {
+        Searcher {
+            deadline: Instant::now() + limit,
+            weights: weights,
+            nodes: 0,
+            table: HashMap::new(),
+        }
+    }
+
+    /// Finds the best move by searching deeper and deeper until time runs out.
+    /// The result of the deepest completed iteration is returned.
+    pub fn find_best_move(&mut self, turn: &Turn) -> Result<Coord> {
+        let side = try!(turn.get_state().ok_or(reversi::ReversiError::EndedGame));
+        let mut moves = turn.get_legal_moves();
+        if moves.len() == 1 {
+            return Ok(moves[0]);
+        }
+
+        let mut best = moves[0];
+        for depth in 1..(max_depth(turn) + 1) {
+            // Search the best move of the previous iteration first
+            self.order(&mut moves, turn.get_size(), Some(index(best)));
+            match self.search_root(turn, side, &moves, depth) {
+                Ok((coord, _)) => best = coord,
+                Err(Timeout) => break,
+            }
+            if Instant::now() >= self.deadline {
+                break;
+            }
+        }
+
+        Ok(best)
+    }
+
+    /// Scores every legal move by searching deeper and deeper until time runs out, e.g. to analyse a game.
+    /// Scores are from the point of view of the player to move, and the best moves come first.
+    pub fn score_moves(&mut self, turn: &Turn) -> Result<Vec<(Coord, i32)>> {
+        let side = try!(turn.get_state().ok_or(reversi::ReversiError::EndedGame));
+        let moves = turn.get_legal_moves();
+
+        // Without even one completed iteration, the moves are scored by a static evaluation
+        let mut scores = Vec::with_capacity(moves.len());
+        for &coord in &moves {
+            let child = turn.make_move(coord).expect("Legal moves are legal");
+            scores.push((coord, match child.get_state() {
+                None => final_score(&child, side),
+                Some(_) => try!(evaluate(&child, side, &self.weights)),
+            }));
+        }
+
+        'deepening: for depth in 2..(max_depth(turn) + 1) {
+            let mut iteration = Vec::with_capacity(moves.len());
+            for &coord in &moves {
+                let child = turn.make_move(coord).expect("Legal moves are legal");
+                match self.child_value(&child, side, depth - 1, -INFINITY, INFINITY) {
+                    Ok(value) => iteration.push((coord, value)),
+                    Err(Timeout) => break 'deepening,
+                }
+            }
+            scores = iteration;
+            if Instant::now() >= self.deadline {
+                break;
+            }
+        }
+
+        scores.sort_by(|a, b| b.1.cmp(&a.1));
+        Ok(scores)
+    }
+
+    /// Searches all root moves to a given depth.
+    fn search_root(&mut self, turn: &Turn, side: reversi::Side, moves: &[Coord], depth: u8) -> ::std::result::Result<(Coord, i32), Timeout> {
+        let mut alpha = -INFINITY;
+        let mut best = moves[0];
+        for &coord in moves {
+            let child = turn.make_move(coord).expect("Legal moves are legal");
+            let value = try!(self.child_value(&child, side, depth - 1, alpha, INFINITY));
+            if value > alpha {
+                alpha = value;
+                best = coord;
+            }
+        }
+        self.store(turn, side, depth, alpha, Bound::Exact, Some(index(best)));
+        Ok((best, alpha))
+    }
+
+    /// Value of a child position from the point of view of `side`, the player who moved into it.
+    /// If the opponent has to pass, `side` moves again and the value is not negated.
+    fn child_value(&mut self, child: &Turn, side: reversi::Side, depth: u8, alpha: i32, beta: i32) -> ::std::result::Result<i32, Timeout> {
+        match child.get_state() {
+            None => Ok(final_score(child, side)),
+            Some(next) if next == side => self.negamax(child, side, depth, alpha, beta),
+            Some(next) => self.negamax(child, next, depth, -beta, -alpha).map(|value| -value),
+        }
+    }
+
+    /// Negamax with alpha-beta pruning: the value of a running position from the point of view of `side`, the player to move.
+    fn negamax(&mut self, turn: &Turn, side: reversi::Side, depth: u8, mut alpha: i32, mut beta: i32) -> ::std::result::Result<i32, Timeout> {
+        self.nodes += 1;
+        if self.nodes % CLOCK_CHECK == 0 && Instant::now() >= self.deadline {
+            return Err(Timeout);
+        }
+
+        if depth == 0 {
+            return Ok(evaluate(turn, side, &self.weights).expect("Cells of the board are in bounds"));
+        }
+
+        // Look the position up in the transposition table
+        let original_alpha = alpha;
+        let mut table_move = None;
+        if let Some(entry) = self.table.get(&key(turn, side)) {
+            table_move = entry.best;
+            if entry.depth >= depth {
+                match entry.bound {
+                    Bound::Exact => return Ok(entry.value),
+                    Bound::Lower => alpha = cmp::max(alpha, entry.value),
+                    Bound::Upper => beta = cmp::min(beta, entry.value),
+                }
+                if alpha >= beta {
+                    return Ok(entry.value);
+                }
+            }
+        }
+
+        let mut moves = turn.get_legal_moves();
+        self.order(&mut moves, turn.get_size(), table_move);
+
+        let mut best_value = -INFINITY;
+        let mut best_move = None;
+        for coord in moves {
+            let child = turn.make_move(coord).expect("Legal moves are legal");
+            let value = try!(self.child_value(&child, side, depth - 1, alpha, beta));
+            if value > best_value {
+                best_value = value;
+                best_move = Some(index(coord));
+            }
+            alpha = cmp::max(alpha, value);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best_value <= original_alpha {
+            Bound::Upper
+        } else if best_value >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.store(turn, side, depth, best_value, bound, best_move);
+
+        Ok(best_value)
+    }
+
+    /// Sorts moves: the move suggested by the transposition table first, then by static priority.
+    fn order(&self, moves: &mut Vec<Coord>, size: usize, first: Option<u8>) {
+        moves.sort_by_key(|&coord| {
+            if Some(index(coord)) == first {
+                i8::min_value()
+            } else {
+                -cell_priority(coord, size)
+            }
+        });
+    }
+
+    /// Stores a search result in the transposition table.
+    fn store(&mut self, turn: &Turn, side: reversi::Side, depth: u8, value: i32, bound: Bound, best: Option<u8>) {
+        if self.table.len() >= TABLE_SIZE {
+            self.table.clear();
+        }
+        self.table.insert(key(turn, side), Entry {
+            depth: depth,
+            value: value,
+            bound: bound,
+            best: best,
+        });
+    }
+}
@@ -0,0 +1,160 @@
+//! Headless AI-vs-AI tournaments.
+//! Two AI configurations play a number of games against each other without any board being printed.
+//! Games come in pairs starting from the same random opening, one with each configuration as Dark,
+//! so that neither gets the better openings by luck. Results are summed up with an Elo estimate.
+
+use std::io;
+use std::time::{Duration, Instant};
+use rand::{ChaChaRng, Rng, SeedableRng};
+use reversi::{self, Side};
+use reversi::game::Game;
+use reversi::turn::Turn;
+use ai_player::{AiPlayer, Strength};
+use evaluation::Weights;
+
+/// Random moves played before the AIs take over, unless set otherwise.
+pub const OPENING_MOVES: usize = 4;
+
+/// An AI configuration taking part in a tournament.
+pub struct Contestant {
+    /// How it is named in the results, as it was given.
+    pub name: String,
+    pub strength: Strength,
+    pub weights: Weights,
+}
+
+impl Contestant {
+    /// Parses a configuration `STRENGTH[:WEIGHTS_FILE]`, where the strength is `weak`, `medium`, `strong`
+    /// or a thinking time in seconds, e.g. `medium` or `0.5:tuned_weights.txt`.
+    /// Without a weights file, the given default weights are used.
+    pub fn parse(config: &str, default_weights: Weights) -> io::Result<Contestant> {
+        let mut parts = config.splitn(2, ':');
+        let strength = match &*parts.next().unwrap_or("").to_lowercase() {
+            "weak" => Strength::Weak,
+            "medium" => Strength::Medium,
+            "strong" => Strength::Strong,
+            secs => match secs.parse::<f64>() {
+                Ok(secs) if secs > 0f64 => Strength::Timed(Duration::from_millis((secs * 1000f64) as u64)),
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid AI strength in '{}'", config))),
+            },
+        };
+        let weights = match parts.next() {
+            Some(path) => try!(Weights::load(path).map_err(|err| {
+                io::Error::new(err.kind(), format!("cannot read weights from '{}': {}", path, err))
+            })),
+            None => default_weights,
+        };
+        Ok(Contestant {
+            name: config.to_string(),
+            strength: strength,
+            weights: weights,
+        })
+    }
+
+    fn player(&self, seed: u32) -> AiPlayer {
+        AiPlayer::new(self.strength, seed).with_weights(self.weights)
+    }
+}
+
+/// What happened in one game, from the point of view of the first contestant.
+struct Outcome {
+    differential: i32,
+    /// Time spent thinking and number of moves, for each contestant.
+    thinking: [(Duration, u32); 2],
+}
+
+/// Plays a game from the given opening. The first contestant plays Dark if `first_is_dark`.
+fn play(first: &AiPlayer, second: &AiPlayer, first_is_dark: bool, opening: &Turn) -> reversi::Result<Outcome> {
+    let (dark, light) = if first_is_dark { (first, second) } else { (second, first) };
+    let mut game: Game<::OtherAction, _, _> = Game::starting_from(dark, light, opening.clone());
+    let mut thinking = [(Duration::from_secs(0), 0); 2];
+
+    while let Some(side) = game.get_current_state() {
+        let start = Instant::now();
+        try!(game.play_turn());
+        let index = if (side == Side::Dark) == first_is_dark { 0 } else { 1 };
+        thinking[index].0 += start.elapsed();
+        thinking[index].1 += 1;
+    }
+
+    let (score_dark, score_light) = game.get_current_score();
+    let dark_differential = score_dark as i32 - score_light as i32;
+    Ok(Outcome {
+        differential: if first_is_dark { dark_differential } else { -dark_differential },
+        thinking: thinking,
+    })
+}
+
+/// Plays random legal moves from the start of a game.
+fn random_opening<R: Rng>(rng: &mut R, moves: usize) -> reversi::Result<Turn> {
+    let mut turn = Turn::first_turn();
+    for _ in 0..moves {
+        let legal_moves = turn.get_legal_moves();
+        if legal_moves.is_empty() {
+            break;
+        }
+        turn = try!(turn.make_move(legal_moves[rng.gen_range(0, legal_moves.len())]));
+    }
+    Ok(turn)
+}
+
+/// The Elo difference matching an expected score, between 0 and 1 excluded.
+fn elo_difference(score: f64) -> f64 {
+    -400f64 * (1f64 / score - 1f64).log10()
+}
+
+fn average_millis(time: Duration, moves: u32) -> f64 {
+    if moves == 0 {
+        0f64
+    } else {
+        (time.as_secs() as f64 * 1000f64 + time.subsec_nanos() as f64 / 1_000_000f64) / moves as f64
+    }
+}
+
+/// Plays `games` games (rounded up to an even number) between two contestants and prints the results.
+/// Openings and AIs' random choices only depend on `seed`.
+pub fn run(first: &Contestant, second: &Contestant, games: u32, opening_moves: usize, seed: u32) -> reversi::Result<()> {
+    let mut rng = ChaChaRng::from_seed(&[seed]);
+    let (mut wins, mut draws, mut losses) = (0u32, 0u32, 0u32);
+    let mut differential = 0i32;
+    let mut thinking = [(Duration::from_secs(0), 0u32); 2];
+
+    println!("\t{} vs {}", first.name, second.name);
+    let pairs = (games + 1) / 2;
+    for pair in 0..pairs {
+        let opening = try!(random_opening(&mut rng, opening_moves));
+        for &first_is_dark in &[true, false] {
+            let (first_seed, second_seed) = (rng.gen(), rng.gen());
+            let outcome = try!(play(&first.player(first_seed), &second.player(second_seed), first_is_dark, &opening));
+
+            match outcome.differential {
+                0 => draws += 1,
+                diff if diff > 0 => wins += 1,
+                _ => losses += 1,
+            }
+            differential += outcome.differential;
+            for (total, game) in thinking.iter_mut().zip(outcome.thinking.iter()) {
+                total.0 += game.0;
+                total.1 += game.1;
+            }
+            println!("\tGame {}: {} as {}, disks {:+}", 2 * pair + if first_is_dark { 1 } else { 2 }, first.name,
+                     if first_is_dark { "Dark" } else { "Light" }, outcome.differential);
+        }
+    }
+
+    let played = 2 * pairs;
+    println!("");
+    println!("\t{} wins, {} draws, {} losses for {}", wins, draws, losses, first.name);
+    println!("\tAverage disk differential: {:+.2}", differential as f64 / played as f64);
+    println!("\tTime per move: {:.1} ms for {}, {:.1} ms for {}",
+             average_millis(thinking[0].0, thinking[0].1), first.name,
+             average_millis(thinking[1].0, thinking[1].1), second.name);
+
+    // A perfect score has no finite Elo estimate: count it as half a game less than perfect
+    let score = (wins as f64 + draws as f64 / 2f64) / played as f64;
+    let bound = 0.5f64 / played as f64;
+    let estimate = elo_difference(score.max(bound).min(1f64 - bound));
+    let qualifier = if score == 0f64 { "at most " } else if score == 1f64 { "at least " } else { "" };
+    println!("\tElo difference: {}{:+.0} for {}", qualifier, estimate, first.name);
+    Ok(())
+}
@@ -0,0 +1,71 @@
+//! Self-play tuning of the evaluation weights.
+//! Each round, one weight is nudged at random and the modified evaluation plays a few games against the current one,
+//! with both colours. The change is kept if it won more disks than it lost, and the weights file is written back.
+
+use std::io;
+use rand::{ChaChaRng, Rng, SeedableRng};
+use reversi;
+use reversi::game::Game;
+use ai_player::{AiPlayer, Strength};
+use evaluation::{Weights, FEATURES};
+
+/// Games played by each candidate, half of them as Dark and half as Light.
+const GAMES_PER_ROUND: u32 = 20;
+
+/// How much a weight is nudged.
+const STEP: f64 = 0.1;
+
+/// Plays a game and returns the final disk differential for Light.
+fn play(dark: &AiPlayer, light: &AiPlayer) -> reversi::Result<i16> {
+    let mut game: Game<::OtherAction, _, _> = Game::new(dark, light);
+    while !game.is_ended() {
+        try!(game.play_turn());
+    }
+    let (dark_score, light_score) = game.get_current_score();
+    Ok(light_score as i16 - dark_score as i16)
+}
+
+/// Tunes the weights stored in `path` (or the default ones if there are none yet) for a number of rounds.
+/// The games played only depend on `seed` and on the starting weights.
+pub fn tune(path: &str, rounds: u32, seed: u32) -> io::Result<()> {
+    let mut weights = match Weights::load(path) {
+        Ok(weights) => weights,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Weights::default(),
+        Err(err) => return Err(err),
+    };
+    let mut rng = ChaChaRng::from_seed(&[seed]);
+
+    for round in 0..rounds {
+        let feature = rng.gen_range(0, FEATURES.len());
+        let step = if rng.gen() { STEP } else { -STEP };
+        let mut candidate = weights;
+        candidate.values[feature] += step;
+
+        // Disks won by the candidate over all games
+        let mut balance = 0i32;
+        for game in 0..GAMES_PER_ROUND {
+            let seed = rng.gen();
+            let current_player = AiPlayer::new(Strength::Weak, seed).with_weights(weights);
+            let candidate_player = AiPlayer::new(Strength::Weak, seed).with_weights(candidate);
+            balance += if game % 2 == 0 {
+                try!(play(&current_player, &candidate_player).map_err(to_io)) as i32
+            } else {
+                -try!(play(&candidate_player, &current_player).map_err(to_io)) as i32
+            };
+        }
+
+        let accepted = balance > 0;
+        println!("\tRound {}: {} {:.2} -> {:.2}, disks {:+}, {}", round + 1, FEATURES[feature],
+                 weights.values[feature], candidate.values[feature], balance, if accepted { "kept" } else { "dropped" });
+        if accepted {
+            weights = candidate;
+            try!(weights.save(path));
+        }
+    }
+
+    Ok(())
+}
+
+fn to_io(err: reversi::ReversiError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err)
+}